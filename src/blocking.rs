@@ -0,0 +1,146 @@
+//!A blocking RCON connection for synchronous programs which don't want to pull in a tokio runtime. It shares the
+//! packet codec and protocol handling with [`crate::rcon::RCONConnection`], so logging in and multi-packet responses
+//! behave exactly the same.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use tokio_util::bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::RconError;
+use crate::rcon::{
+    auth_bytes, auth_step, command_packets, command_step, next_id, Encoding, RCONPacket, RconCodec,
+    Step,
+};
+
+///A blocking connection to an RCON server over plain TCP
+pub struct RCONConnection {
+    next_id: i32,
+    stream: TcpStream,
+    codec: RconCodec,
+    encoding: Encoding,
+    read_buffer: BytesMut,
+}
+
+impl RCONConnection {
+    ///Connects to the server. `connect_timeout` applies to each address the name resolves to, None waits forever.
+    pub fn new(
+        address: &str,
+        port: u16,
+        connect_timeout: Option<Duration>,
+    ) -> Result<RCONConnection, RconError> {
+        let host = String::from(address) + ":" + port.to_string().as_str();
+
+        //Resolving separately from connecting is the only way to tell a typo in the address apart from an offline server
+        let addrs: Vec<_> = host
+            .to_socket_addrs()
+            .map_err(|_| RconError::Dns(String::from(address)))?
+            .collect();
+
+        let mut last_error = RconError::Dns(String::from(address));
+        for addr in addrs {
+            let stream = match connect_timeout {
+                Some(t) => TcpStream::connect_timeout(&addr, t),
+                None => TcpStream::connect(addr),
+            };
+
+            match stream {
+                Ok(stream) => {
+                    return Ok(RCONConnection {
+                        next_id: 1,
+                        stream,
+                        codec: RconCodec::default(),
+                        encoding: Encoding::default(),
+                        read_buffer: BytesMut::new(),
+                    })
+                }
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                    last_error = RconError::ConnectionRefused(host.clone())
+                }
+                Err(e) => last_error = map_io_error(e),
+            }
+        }
+
+        return Err(last_error);
+    }
+
+    ///Sets how long to wait for each read from the server. None waits forever.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<(), RconError> {
+        self.stream.set_read_timeout(timeout)?;
+        Ok(())
+    }
+
+    ///Sets how long each write may block. None waits forever.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> Result<(), RconError> {
+        self.stream.set_write_timeout(timeout)?;
+        Ok(())
+    }
+
+    ///Sets the largest packet accepted from the server, in bytes. Larger packets fail with [`RconError::MalformedPacket`].
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.codec = RconCodec::new(size);
+    }
+
+    ///Sets how response bodies are decoded. Defaults to [`Encoding::Lossy`].
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    ///Logs in. Has to succeed before the server accepts any commands.
+    pub fn auth(&mut self, password: &str) -> Result<(), RconError> {
+        let id = next_id(&mut self.next_id);
+        self.stream
+            .write_all(&auth_bytes(id, password)?)
+            .map_err(map_io_error)?;
+
+        while auth_step(id, &self.read_packet()?)? == Step::Continue {}
+
+        Ok(())
+    }
+
+    ///Sends a command and returns the full response body, reassembled if the server split it over several packets.
+    pub fn send_command(&mut self, command: &str) -> Result<String, RconError> {
+        let id = next_id(&mut self.next_id);
+        let sentinel_id = next_id(&mut self.next_id);
+        for packet in command_packets(id, sentinel_id, command)? {
+            self.write_packet(packet)?;
+        }
+
+        let mut body = Vec::new();
+        while command_step(id, sentinel_id, self.read_packet()?, &mut body) == Step::Continue {}
+
+        return self.encoding.decode(&body);
+    }
+
+    fn write_packet(&mut self, packet: RCONPacket) -> Result<(), RconError> {
+        let mut buf = BytesMut::new();
+        self.codec.encode(packet, &mut buf)?;
+        self.stream.write_all(&buf).map_err(map_io_error)?;
+
+        Ok(())
+    }
+
+    fn read_packet(&mut self) -> Result<RCONPacket, RconError> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(packet) = self.codec.decode(&mut self.read_buffer)? {
+                return Ok(packet);
+            }
+
+            match self.stream.read(&mut buf).map_err(map_io_error)? {
+                0 => return Err(RconError::Disconnected),
+                n => self.read_buffer.extend_from_slice(&buf[..n]),
+            }
+        }
+    }
+}
+
+//Socket timeouts surface as WouldBlock on Unix and TimedOut on Windows
+fn map_io_error(e: io::Error) -> RconError {
+    return match e.kind() {
+        io::ErrorKind::WouldBlock => RconError::Timeout,
+        _ => e.into(),
+    };
+}
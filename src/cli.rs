@@ -0,0 +1,531 @@
+use std::path::PathBuf;
+
+use argh::FromArgs;
+use frcon::games::Game;
+use frcon::rcon;
+use frcon::theme::Theme;
+use frcon::transcript::LogFormat;
+use regex::Regex;
+
+///Names which switch the command line over to the subcommand form. Anything else is parsed the old way, as flags
+/// followed by the commands to run.
+pub const SUBCOMMANDS: [&str; 5] = ["shell", "exec", "watch", "servers", "completions"];
+
+///The options for picking a server and connecting to it, shared by every command that connects
+#[derive(Clone)]
+pub struct ConnectArgs {
+    pub server: Option<String>,
+    pub config: Option<PathBuf>,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub password: Option<String>,
+    pub password_file: Option<PathBuf>,
+    pub game: Option<Game>,
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    pub theme: Option<Theme>,
+    pub max_response_size: usize,
+    pub encoding: rcon::Encoding,
+    pub command_limit: Option<usize>,
+    pub tls: bool,
+    pub tls_ca: Option<PathBuf>,
+    pub tls_fingerprint: Option<String>,
+    pub tls_sni: Option<String>,
+}
+
+impl Default for ConnectArgs {
+    fn default() -> Self {
+        ConnectArgs {
+            server: None,
+            config: None,
+            address: None,
+            port: None,
+            password: None,
+            password_file: None,
+            game: None,
+            connect_timeout: None,
+            timeout: None,
+            theme: None,
+            max_response_size: rcon::DEFAULT_MAX_RESPONSE_SIZE,
+            encoding: rcon::Encoding::default(),
+            command_limit: None,
+            tls: false,
+            tls_ca: None,
+            tls_fingerprint: None,
+            tls_sni: None,
+        }
+    }
+}
+
+///argh has no way to share options between commands, so this stamps the connection options onto each command that
+/// needs them, next to the command's own fields
+macro_rules! connect_command {
+    (
+        $(#[$attr:meta])*
+        struct $name:ident {
+            $($fields:tt)*
+        }
+    ) => {
+        #[derive(FromArgs)]
+        $(#[$attr])*
+        pub struct $name {
+            #[argh(option, description = "server profile from the config file", short = 'S')]
+            pub server: Option<String>,
+
+            #[argh(option, description = "config file to read profiles from")]
+            pub config: Option<PathBuf>,
+
+            #[argh(option, description = "address of the server", short = 'a')]
+            pub address: Option<String>,
+
+            #[argh(option, description = "RCON port of the server (default 25575)", short = 'p')]
+            pub port: Option<u16>,
+
+            #[argh(
+                option,
+                description = "RCON password. Visible in shell history and ps, prefer FRCON_PASSWORD, --password-file or the prompt",
+                short = 'P'
+            )]
+            pub password: Option<String>,
+
+            #[argh(option, description = "file containing the RCON password")]
+            pub password_file: Option<PathBuf>,
+
+            #[argh(
+                option,
+                description = "enables game specific prompt features (minecraft)",
+                short = 'g'
+            )]
+            pub game: Option<Game>,
+
+            #[argh(
+                option,
+                description = "seconds to wait for the connection to open, 0 waits forever (default 10)"
+            )]
+            pub connect_timeout: Option<u64>,
+
+            #[argh(
+                option,
+                description = "seconds to wait for each response, 0 waits forever (default 30)"
+            )]
+            pub timeout: Option<u64>,
+
+            #[argh(option, description = "colour scheme of the shell (dark, light)")]
+            pub theme: Option<Theme>,
+
+            #[argh(
+                option,
+                description = "largest packet in bytes accepted from the server",
+                default = "rcon::DEFAULT_MAX_RESPONSE_SIZE"
+            )]
+            pub max_response_size: usize,
+
+            #[argh(
+                option,
+                description = "how to decode responses which aren't UTF-8 (strict, lossy, latin1)",
+                default = "rcon::Encoding::Lossy"
+            )]
+            pub encoding: rcon::Encoding,
+
+            #[argh(
+                option,
+                description = "longest command in bytes the server accepts, if it differs from the game's default"
+            )]
+            pub command_limit: Option<usize>,
+
+            #[argh(switch, description = "connect over TLS")]
+            pub tls: bool,
+
+            #[argh(option, description = "PEM file with the CA certificates to trust for TLS")]
+            pub tls_ca: Option<PathBuf>,
+
+            #[argh(option, description = "SHA-256 fingerprint the server's TLS certificate must match")]
+            pub tls_fingerprint: Option<String>,
+
+            #[argh(option, description = "server name to use for TLS instead of the address")]
+            pub tls_sni: Option<String>,
+
+            $($fields)*
+        }
+
+        impl $name {
+            pub fn connect_args(&self) -> ConnectArgs {
+                return ConnectArgs {
+                    server: self.server.clone(),
+                    config: self.config.clone(),
+                    address: self.address.clone(),
+                    port: self.port,
+                    password: self.password.clone(),
+                    password_file: self.password_file.clone(),
+                    game: self.game.clone(),
+                    connect_timeout: self.connect_timeout,
+                    timeout: self.timeout,
+                    theme: self.theme,
+                    max_response_size: self.max_response_size,
+                    encoding: self.encoding,
+                    command_limit: self.command_limit,
+                    tls: self.tls,
+                    tls_ca: self.tls_ca.clone(),
+                    tls_fingerprint: self.tls_fingerprint.clone(),
+                    tls_sni: self.tls_sni.clone(),
+                };
+            }
+        }
+    };
+}
+
+connect_command! {
+    ///The original command line, kept working for existing scripts. Runs the given commands, or opens a shell if there
+    /// are none.
+    #[argh(
+        description = "Minecraft RCON Implementation for Rust",
+        note = "The subcommand form (frcon shell, exec, servers, completions) is preferred. Run frcon help <subcommand> for details."
+    )]
+    struct LegacyArgs {
+        #[argh(
+            positional,
+            description = "will be executed and shell mode will not be entered. The first one is used as the profile if it names one"
+        )]
+        pub commands: Vec<String>,
+
+        #[argh(
+            option,
+            description = "seconds to wait between each passed command",
+            short = 'w'
+        )]
+        pub wait: Option<u64>,
+
+        #[argh(
+            switch,
+            description = "disables output printing for passed commands",
+            short = 's'
+        )]
+        pub silent: Option<bool>,
+
+        #[argh(
+            option,
+            description = "how to print responses (text, json). json prints one object per command, as NDJSON",
+            short = 'o',
+            default = "OutputFormat::Text"
+        )]
+        pub output: OutputFormat,
+
+        #[argh(
+            switch,
+            description = "exit 0 even when the server answers a command with an error, like an unknown command"
+        )]
+        pub no_fail_on_error: bool,
+
+        #[argh(switch, description = "prints version information", short = 'v')]
+        pub version: Option<bool>,
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(description = "Minecraft RCON Implementation for Rust")]
+pub struct Cli {
+    #[argh(subcommand)]
+    pub command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    Shell(ShellCommand),
+    Exec(ExecCommand),
+    Watch(WatchCommand),
+    Servers(ServersCommand),
+    Completions(CompletionsCommand),
+}
+
+connect_command! {
+    #[argh(subcommand, name = "shell", description = "open an interactive shell on a server")]
+    struct ShellCommand {
+        #[argh(positional, description = "server profile from the config file")]
+        pub profile: Option<String>,
+
+        #[argh(
+            option,
+            description = "append a timestamped transcript of every command and response to this file"
+        )]
+        pub log_session: Option<PathBuf>,
+
+        #[argh(option, description = "format of the session log (text, jsonl), text by default")]
+        pub log_format: Option<LogFormat>,
+    }
+}
+
+connect_command! {
+    #[argh(
+        subcommand,
+        name = "exec",
+        description = "run commands on a server and exit",
+        example = "frcon exec survival -- list \"say Restarting in 5 minutes\"",
+        example = "frcon exec survival -f maintenance.rcon --fail-fast",
+        example = "cat commands.rcon | frcon exec survival -",
+        note = "Scripts have one command per line. Blank lines and lines starting with # are skipped. @wait 5s pauses, and @expect <regex> fails unless the previous response matches."
+    )]
+    struct ExecCommand {
+        #[argh(
+            positional,
+            description = "server profile, unless -a or -S is given, followed by the commands to run"
+        )]
+        pub commands: Vec<String>,
+
+        #[argh(
+            option,
+            description = "seconds to wait between each command given on the command line. Scripts use @wait",
+            short = 'w'
+        )]
+        pub wait: Option<u64>,
+
+        #[argh(
+            option,
+            description = "script of commands to run, one per line. - reads it from stdin",
+            short = 'f'
+        )]
+        pub file: Option<PathBuf>,
+
+        #[argh(
+            option,
+            description = "how to print responses (text, json). json prints one object per command, as NDJSON",
+            short = 'o',
+            default = "OutputFormat::Text"
+        )]
+        pub output: OutputFormat,
+
+        #[argh(
+            switch,
+            description = "exit 0 even when the server answers a command with an error, like an unknown command"
+        )]
+        pub no_fail_on_error: bool,
+
+        #[argh(
+            switch,
+            description = "stop at the first command that fails or response that doesn't match its @expect"
+        )]
+        pub fail_fast: bool,
+
+        #[argh(
+            switch,
+            description = "disables output printing for commands",
+            short = 's'
+        )]
+        pub silent: Option<bool>,
+    }
+}
+
+connect_command! {
+    #[argh(
+        subcommand,
+        name = "watch",
+        description = "run a command over and over, highlighting what changed",
+        example = "frcon watch -n 5 survival forceload query",
+        example = "frcon watch survival list --until \"Steve\""
+    )]
+    struct WatchCommand {
+        #[argh(
+            positional,
+            description = "server profile, unless -a or -S is given, followed by the command to run"
+        )]
+        pub command: Vec<String>,
+
+        #[argh(
+            option,
+            description = "seconds between runs (default 2)",
+            short = 'n',
+            default = "2.0"
+        )]
+        pub interval: f64,
+
+        #[argh(option, description = "stop once the response matches this regex")]
+        pub until: Option<Regex>,
+
+        #[argh(option, description = "beep when the response starts matching this regex")]
+        pub beep: Option<Regex>,
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(
+    subcommand,
+    name = "servers",
+    description = "manage the server profiles in the config file"
+)]
+pub struct ServersCommand {
+    #[argh(option, description = "config file to use instead of the default")]
+    pub config: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    pub command: ServersSubcommand,
+}
+
+//only ever built once, so the size of the add variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum ServersSubcommand {
+    Add(ServersAdd),
+    List(ServersList),
+    Remove(ServersRemove),
+    Test(ServersTest),
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "add", description = "add a server profile")]
+pub struct ServersAdd {
+    #[argh(positional, description = "name of the profile")]
+    pub name: String,
+
+    #[argh(option, description = "address of the server", short = 'a')]
+    pub address: String,
+
+    #[argh(option, description = "RCON port of the server", short = 'p')]
+    pub port: Option<u16>,
+
+    #[argh(
+        option,
+        description = "game of the server (minecraft, generic)",
+        short = 'g'
+    )]
+    pub game: Option<Game>,
+
+    #[argh(option, description = "environment variable to read the password from")]
+    pub password_env: Option<String>,
+
+    #[argh(option, description = "file to read the password from")]
+    pub password_file: Option<PathBuf>,
+
+    #[argh(
+        option,
+        description = "password to store in the config file. Kept in plain text, prefer --password-env or --password-file",
+        short = 'P'
+    )]
+    pub password: Option<String>,
+
+    #[argh(option, description = "seconds to wait for the connection to open")]
+    pub connect_timeout: Option<u64>,
+
+    #[argh(option, description = "seconds to wait for each response")]
+    pub timeout: Option<u64>,
+
+    #[argh(option, description = "colour scheme of the shell (dark, light)")]
+    pub theme: Option<Theme>,
+
+    #[argh(
+        option,
+        description = "file the shell appends a transcript of every command and response to"
+    )]
+    pub log_session: Option<PathBuf>,
+
+    #[argh(option, description = "format of the session log (text, jsonl)")]
+    pub log_format: Option<LogFormat>,
+
+    #[argh(switch, description = "connect over TLS")]
+    pub tls: bool,
+
+    #[argh(
+        option,
+        description = "PEM file with the CA certificates to trust for TLS"
+    )]
+    pub tls_ca: Option<PathBuf>,
+
+    #[argh(
+        option,
+        description = "SHA-256 fingerprint the server's TLS certificate must match"
+    )]
+    pub tls_fingerprint: Option<String>,
+
+    #[argh(
+        option,
+        description = "server name to use for TLS instead of the address"
+    )]
+    pub tls_sni: Option<String>,
+
+    #[argh(
+        switch,
+        description = "replace the profile if it already exists",
+        short = 'f'
+    )]
+    pub force: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "list", description = "list the server profiles")]
+pub struct ServersList {
+    #[argh(switch, description = "only print the profile names")]
+    pub names: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "remove", description = "remove a server profile")]
+pub struct ServersRemove {
+    #[argh(positional, description = "name of the profile")]
+    pub name: String,
+}
+
+#[derive(FromArgs)]
+#[argh(
+    subcommand,
+    name = "test",
+    description = "check that a profile can connect and log in"
+)]
+pub struct ServersTest {
+    #[argh(positional, description = "name of the profile")]
+    pub name: String,
+}
+
+#[derive(FromArgs)]
+#[argh(
+    subcommand,
+    name = "completions",
+    description = "print a completion script for a shell",
+    example = "frcon completions bash > /etc/bash_completion.d/frcon"
+)]
+pub struct CompletionsCommand {
+    #[argh(
+        positional,
+        description = "shell to print the script for (bash, zsh, fish)"
+    )]
+    pub shell: CompletionShell,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format {}. Expected text or json",
+                s
+            )),
+        };
+    }
+}
+
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::str::FromStr for CompletionShell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "bash" => Ok(CompletionShell::Bash),
+            "zsh" => Ok(CompletionShell::Zsh),
+            "fish" => Ok(CompletionShell::Fish),
+            _ => Err(format!("Unknown shell {}. Expected bash, zsh or fish", s)),
+        };
+    }
+}
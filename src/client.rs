@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use crossterm::style::ContentStyle;
+use tokio::io::{AsyncRead, AsyncWrite};
+use zeroize::Zeroizing;
+
+use crate::error::RconError;
+use crate::games::{Game, GameMapper};
+use crate::rcon::{Encoding, RCONConnection, DEFAULT_MAX_RESPONSE_SIZE, MAX_COMMAND_SIZE};
+use crate::transport::{TlsOptions, Transport};
+
+///An authenticated connection to an RCON server, along with the game profile used to format its responses.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), frcon::RconError> {
+/// use frcon::{Game, RconClient};
+///
+/// let mut client = RconClient::builder()
+///     .address("mc.example.com")
+///     .password("hunter2")
+///     .game(Game::MINECRAFT)
+///     .connect()
+///     .await?;
+///
+/// let players = client.send_command("list").await?;
+/// for (line, _style) in client.format_response(&players) {
+///     println!("{}", line);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RconClient<S = Transport> {
+    conn: RCONConnection<S>,
+    password: Zeroizing<String>,
+    game: Game,
+    command_limit: Option<usize>,
+}
+
+impl RconClient {
+    ///Returns a builder with the defaults: localhost, port 25575, no password, the generic game profile,
+    /// a 10 second connect timeout and a 30 second response timeout.
+    pub fn builder() -> RconClientBuilder {
+        return RconClientBuilder::default();
+    }
+
+    ///Opens a new connection to the same server and logs in again with the stored password.
+    pub async fn reconnect(&mut self) -> Result<(), RconError> {
+        self.conn.reconnect().await?;
+        self.conn.auth(&self.password).await
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> RconClient<S> {
+    ///Logs in over an already open connection, for transports the builder doesn't know about.
+    pub async fn from_connection(
+        mut conn: RCONConnection<S>,
+        password: &str,
+        game: Game,
+    ) -> Result<RconClient<S>, RconError> {
+        conn.auth(password).await?;
+
+        return Ok(RconClient {
+            conn,
+            password: Zeroizing::new(String::from(password)),
+            game,
+            command_limit: None,
+        });
+    }
+
+    ///Sends a command and returns the full response body, reassembled if the server split it over several packets.
+    /// Commands over [`RconClient::command_limit`] are split up if the game allows it, see [`RconClient::split_command`].
+    pub async fn send_command(&mut self, command: &str) -> Result<String, RconError> {
+        let parts = self.split_command(command)?;
+        if parts.len() == 1 {
+            return self.conn.send_command(command).await;
+        }
+
+        let mut responses = Vec::<String>::new();
+        for part in parts {
+            let response = self.conn.send_command(&part).await?;
+            if !response.is_empty() {
+                responses.push(response);
+            }
+        }
+
+        return Ok(responses.join("\n"));
+    }
+
+    ///The longest command the server accepts in bytes. Either set on the builder, or the game's known limit.
+    pub fn command_limit(&self) -> usize {
+        return self
+            .command_limit
+            .or(GameMapper::get_command_limit(&self.game))
+            .unwrap_or(MAX_COMMAND_SIZE)
+            .min(MAX_COMMAND_SIZE);
+    }
+
+    ///Returns the commands `command` will be sent as. That's just the command itself unless it's over the limit and
+    /// the game allows splitting it, like a long `say` in Minecraft. Oversized commands which can't be split are an error,
+    /// as servers tend to drop them without a word.
+    pub fn split_command(&self, command: &str) -> Result<Vec<String>, RconError> {
+        let limit = self.command_limit();
+        if command.len() <= limit {
+            return Ok(vec![String::from(command)]);
+        }
+
+        return match (GameMapper::get_split_fn(&self.game))(command, limit) {
+            Some(parts) => Ok(parts),
+            None => Err(RconError::CommandTooLong {
+                length: command.len(),
+                max: limit,
+            }),
+        };
+    }
+
+    ///The game profile responses are formatted with
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    ///Switches the game profile responses are formatted with, e.g. when a server turns out not to be the configured game
+    pub fn set_game(&mut self, game: Game) {
+        self.game = game;
+    }
+
+    ///Splits a response into lines styled for the client's game
+    pub fn format_response(&self, response: &str) -> Vec<(String, ContentStyle)> {
+        return (GameMapper::get_response_fn(&self.game))(response);
+    }
+
+    ///Known commands of the client's game, used for completion and highlighting
+    pub fn commands(&self) -> Vec<String> {
+        return (GameMapper::get_command_fn(&self.game))();
+    }
+
+    ///Gives access to the underlying connection, e.g. to change timeouts after connecting
+    pub fn connection(&mut self) -> &mut RCONConnection<S> {
+        return &mut self.conn;
+    }
+}
+
+///Options for connecting an [`RconClient`]. Created with [`RconClient::builder`].
+pub struct RconClientBuilder {
+    address: String,
+    port: u16,
+    password: Zeroizing<String>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    tls: Option<TlsOptions>,
+    max_response_size: usize,
+    encoding: Encoding,
+    command_limit: Option<usize>,
+    game: Game,
+}
+
+impl Default for RconClientBuilder {
+    fn default() -> Self {
+        return RconClientBuilder {
+            address: String::from("localhost"),
+            port: 25575,
+            password: Zeroizing::new(String::new()),
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            write_timeout: Some(Duration::from_secs(30)),
+            tls: None,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            encoding: Encoding::default(),
+            command_limit: None,
+            game: Game::GENERIC,
+        };
+    }
+}
+
+impl RconClientBuilder {
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    ///The password to log in with. Kept in memory which is wiped once the client is dropped.
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Zeroizing::new(password.into());
+        self
+    }
+
+    ///How long to wait for the connection to open. None waits forever.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    ///How long to wait for each response, and for each request to be sent. None waits forever.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self.write_timeout = timeout;
+        self
+    }
+
+    ///How long to wait for each packet from the server. None waits forever.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    ///How long sending a packet may take. None waits forever.
+    pub fn write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
+
+    ///Connects over TLS instead of plain TCP
+    pub fn tls(mut self, options: TlsOptions) -> Self {
+        self.tls = Some(options);
+        self
+    }
+
+    ///The largest packet accepted from the server, in bytes. Protects against corrupt or malicious length fields.
+    pub fn max_response_size(mut self, size: usize) -> Self {
+        self.max_response_size = size;
+        self
+    }
+
+    ///How response bodies are decoded. Defaults to replacing invalid UTF-8.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    ///Overrides the game's limit on command length in bytes, e.g. for a modded server which accepts more
+    pub fn command_limit(mut self, limit: Option<usize>) -> Self {
+        self.command_limit = limit;
+        self
+    }
+
+    pub fn game(mut self, game: Game) -> Self {
+        self.game = game;
+        self
+    }
+
+    ///Connects and logs in
+    pub async fn connect(self) -> Result<RconClient, RconError> {
+        let mut conn = match &self.tls {
+            Some(tls) => {
+                RCONConnection::new_tls(&self.address, self.port, self.connect_timeout, tls).await?
+            }
+            None => RCONConnection::new(&self.address, self.port, self.connect_timeout).await?,
+        };
+        conn.set_read_timeout(self.read_timeout);
+        conn.set_write_timeout(self.write_timeout);
+        conn.set_max_response_size(self.max_response_size);
+        conn.set_encoding(self.encoding);
+        conn.auth(&self.password).await?;
+
+        return Ok(RconClient {
+            conn,
+            password: self.password,
+            game: self.game,
+            command_limit: self.command_limit,
+        });
+    }
+}
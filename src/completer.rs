@@ -0,0 +1,62 @@
+use reedline::{Completer, DefaultCompleter, Span, Suggestion};
+
+use crate::meta::{self, META_COMMANDS};
+
+///Completes the game's commands, and the shell's own meta-commands and their arguments after a colon
+pub struct RCONCompleter {
+    commands: DefaultCompleter,
+}
+
+impl Completer for RCONCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let before = &line[..pos];
+        let start = before.len() - before.trim_start().len();
+
+        let Some(typed) = before[start..].strip_prefix(':') else {
+            return self.commands.complete(line, pos);
+        };
+
+        return match typed.split_once(char::is_whitespace) {
+            //Still typing the name
+            None => META_COMMANDS
+                .iter()
+                .filter(|c| c.name.starts_with(typed))
+                .map(|c| Suggestion {
+                    value: format!(":{}", c.name),
+                    description: Some(c.description.to_string()),
+                    span: Span::new(start, pos),
+                    append_whitespace: !c.args.is_empty(),
+                    ..Suggestion::default()
+                })
+                .collect(),
+            Some((name, _)) => {
+                let Some(command) = meta::find(name) else {
+                    return Vec::new();
+                };
+                let arg_start = before.rfind(char::is_whitespace).map_or(pos, |i| {
+                    i + before[i..].chars().next().map_or(1, char::len_utf8)
+                });
+                let arg = &before[arg_start..];
+
+                command
+                    .values
+                    .iter()
+                    .filter(|v| v.starts_with(arg))
+                    .map(|v| Suggestion {
+                        value: v.to_string(),
+                        span: Span::new(arg_start, pos),
+                        ..Suggestion::default()
+                    })
+                    .collect()
+            }
+        };
+    }
+}
+
+impl RCONCompleter {
+    pub fn new(commands: Vec<String>) -> RCONCompleter {
+        RCONCompleter {
+            commands: DefaultCompleter::new_with_wordlen(commands, 1),
+        }
+    }
+}
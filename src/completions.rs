@@ -0,0 +1,128 @@
+use crate::cli::CompletionShell;
+
+//The scripts complete subcommands, options and profile names. Profile names are looked up when completing, so they
+// stay current as profiles are added and removed.
+
+const CONNECT_OPTIONS: &str = "-S --server --config -a --address -p --port -P --password --password-file -g --game \
+--connect-timeout --timeout --theme --max-response-size --encoding --command-limit --tls --tls-ca --tls-fingerprint \
+--tls-sni --help";
+
+const BASH: &str = r#"_frcon() {
+    local cur prev sub
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    sub="${COMP_WORDS[1]}"
+
+    case "$prev" in
+        -S|--server) COMPREPLY=($(compgen -W "$(frcon servers list --names 2>/dev/null)" -- "$cur")); return ;;
+        -g|--game) COMPREPLY=($(compgen -W "minecraft generic" -- "$cur")); return ;;
+        --theme) COMPREPLY=($(compgen -W "dark light" -- "$cur")); return ;;
+        --encoding) COMPREPLY=($(compgen -W "strict lossy latin1" -- "$cur")); return ;;
+        -o|--output) COMPREPLY=($(compgen -W "text json" -- "$cur")); return ;;
+        --log-format) COMPREPLY=($(compgen -W "text jsonl" -- "$cur")); return ;;
+        --config|--password-file|--tls-ca|-f|--file|--log-session) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+    esac
+
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "shell exec watch servers completions help" -- "$cur"))
+        return
+    fi
+
+    case "$sub" in
+        shell|exec|watch)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "@OPTIONS@ -w --wait -s --silent -f --file -o --output --fail-fast --no-fail-on-error -n --interval --until --beep --log-session --log-format" -- "$cur"))
+            else
+                COMPREPLY=($(compgen -W "$(frcon servers list --names 2>/dev/null)" -- "$cur"))
+            fi ;;
+        servers)
+            if [[ $COMP_CWORD -eq 2 ]]; then
+                COMPREPLY=($(compgen -W "add list remove test --config" -- "$cur"))
+            else
+                COMPREPLY=($(compgen -W "$(frcon servers list --names 2>/dev/null)" -- "$cur"))
+            fi ;;
+        completions) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")) ;;
+    esac
+}
+complete -F _frcon frcon
+"#;
+
+const ZSH: &str = r#"#compdef frcon
+
+_frcon() {
+    local -a profiles
+    profiles=(${(f)"$(frcon servers list --names 2>/dev/null)"})
+
+    if (( CURRENT == 2 )); then
+        compadd shell exec watch servers completions help
+        return
+    fi
+
+    case "$words[CURRENT-1]" in
+        -S|--server) compadd -a profiles; return ;;
+        -g|--game) compadd minecraft generic; return ;;
+        --theme) compadd dark light; return ;;
+        --encoding) compadd strict lossy latin1; return ;;
+        -o|--output) compadd text json; return ;;
+        --log-format) compadd text jsonl; return ;;
+        --config|--password-file|--tls-ca|-f|--file|--log-session) _files; return ;;
+    esac
+
+    case "$words[2]" in
+        shell|exec|watch)
+            if [[ "$PREFIX" == -* ]]; then
+                compadd -- @OPTIONS@ -w --wait -s --silent -f --file -o --output --fail-fast --no-fail-on-error -n --interval --until --beep --log-session --log-format
+            else
+                compadd -a profiles
+            fi ;;
+        servers)
+            if (( CURRENT == 3 )); then
+                compadd add list remove test --config
+            else
+                compadd -a profiles
+            fi ;;
+        completions) compadd bash zsh fish ;;
+    esac
+}
+
+_frcon "$@"
+"#;
+
+const FISH: &str = r#"function __frcon_profiles
+    frcon servers list --names 2>/dev/null
+end
+
+complete -c frcon -f
+complete -c frcon -n __fish_use_subcommand -a "shell exec watch servers completions help"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -a "(__frcon_profiles)"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -s S -l server -x -a "(__frcon_profiles)"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -s g -l game -x -a "minecraft generic"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -l theme -x -a "dark light"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -l encoding -x -a "strict lossy latin1"
+complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -l config -l password-file -l tls-ca -r -F
+complete -c frcon -n "__fish_seen_subcommand_from exec" -s o -l output -x -a "text json"
+complete -c frcon -n "__fish_seen_subcommand_from exec" -s f -l file -r -F
+complete -c frcon -n "__fish_seen_subcommand_from shell" -l log-session -r -F
+complete -c frcon -n "__fish_seen_subcommand_from shell" -l log-format -x -a "text jsonl"
+for opt in @LONG_OPTIONS@
+    complete -c frcon -n "__fish_seen_subcommand_from shell exec watch" -l $opt
+end
+complete -c frcon -n "__fish_seen_subcommand_from servers; and not __fish_seen_subcommand_from add list remove test" -a "add list remove test"
+complete -c frcon -n "__fish_seen_subcommand_from remove test" -a "(__frcon_profiles)"
+complete -c frcon -n "__fish_seen_subcommand_from completions" -a "bash zsh fish"
+"#;
+
+///The completion script for the given shell
+pub fn script(shell: &CompletionShell) -> String {
+    return match shell {
+        CompletionShell::Bash => BASH.replace("@OPTIONS@", CONNECT_OPTIONS),
+        CompletionShell::Zsh => ZSH.replace("@OPTIONS@", CONNECT_OPTIONS),
+        CompletionShell::Fish => {
+            let long: Vec<&str> = CONNECT_OPTIONS
+                .split(' ')
+                .filter_map(|o| o.strip_prefix("--"))
+                .collect();
+            FISH.replace("@LONG_OPTIONS@", &long.join(" "))
+        }
+    };
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+use crate::theme::Theme;
+use crate::transcript::LogFormat;
+
+///The config file, holding named server profiles. Lives at `$XDG_CONFIG_HOME/frcon/config.toml`, falling back to
+/// `~/.config/frcon/config.toml`.
+///
+/// ```toml
+/// history_size = 5000
+///
+/// [profiles.survival]
+/// address = "mc.example.com"
+/// game = "minecraft"
+/// password_env = "SURVIVAL_RCON_PASSWORD"
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    ///Most commands kept in the shell history of each server, 1000 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_size: Option<usize>,
+    ///Whether to only keep the latest use of each command in the history, on if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_dedup: Option<bool>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+///Everything needed to connect to one server. Only the address is required, the rest falls back to the same defaults
+/// as the command line options.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<Game>,
+
+    ///The password itself. Stored in plain text, so prefer one of the other sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    ///File containing the password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_file: Option<PathBuf>,
+    ///Environment variable containing the password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>,
+
+    ///Seconds, 0 waits forever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    ///Seconds, 0 waits forever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    ///File the shell appends a transcript of every command and response to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_session: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_format: Option<LogFormat>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tls: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ca: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_sni: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+    ///Neither XDG_CONFIG_HOME nor HOME are set
+    NoConfigDir,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "Could not access {}. {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "Invalid config in {}. {}", path.display(), e),
+            ConfigError::Serialize(e) => write!(f, "Could not write config. {}", e),
+            ConfigError::NoConfigDir => write!(
+                f,
+                "Could not find the config directory. Set HOME or XDG_CONFIG_HOME, or pass --config"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    ///Where the config file lives unless another path is given
+    pub fn default_path() -> Result<PathBuf, ConfigError> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => match std::env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".config"),
+                None => return Err(ConfigError::NoConfigDir),
+            },
+        };
+
+        return Ok(base.join("frcon").join("config.toml"));
+    }
+
+    ///Reads the config file. A file that doesn't exist yet is the same as an empty one.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(ConfigError::Io(path.to_path_buf(), e)),
+        };
+
+        return toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e));
+    }
+
+    ///Writes the config file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let contents = toml::to_string_pretty(self).map_err(ConfigError::Serialize)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| ConfigError::Io(dir.to_path_buf(), e))?;
+        }
+
+        //profiles may hold passwords, so only the owner gets to read the file
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options
+            .open(path)
+            .map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        return file
+            .write_all(contents.as_bytes())
+            .map_err(|e| ConfigError::Io(path.to_path_buf(), e));
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        return self.profiles.get(name);
+    }
+}
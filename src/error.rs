@@ -0,0 +1,94 @@
+use std::{fmt, io};
+
+///Everything that can go wrong while talking to an RCON server. Kept separate from io::Error so callers can tell a wrong
+/// password apart from a dead server without digging through error kinds and messages.
+#[derive(Debug)]
+pub enum RconError {
+    ///Nothing is listening on the given address and port
+    ConnectionRefused(String),
+    ///The address couldn't be resolved
+    Dns(String),
+    ///The TLS handshake failed, or the TLS settings are invalid
+    Tls(String),
+    ///The server didn't answer in time
+    Timeout,
+    ///The server answered the login with id -1
+    AuthRejected,
+    ///The command doesn't fit in a single packet
+    CommandTooLong {
+        length: usize,
+        max: usize,
+    },
+    ///A packet which doesn't follow the RCON spec
+    MalformedPacket(String),
+    ///A well formed packet which wasn't what the protocol calls for at that point
+    Protocol(String),
+    ///A response body which isn't valid UTF-8
+    InvalidUtf8,
+    ///The server closed the connection
+    Disconnected,
+    Io(io::Error),
+}
+
+impl RconError {
+    ///Whether the connection is unusable after this error. Errors which only concern a single command leave it intact.
+    pub fn is_fatal(&self) -> bool {
+        return !matches!(
+            self,
+            RconError::CommandTooLong { .. } | RconError::InvalidUtf8
+        );
+    }
+}
+
+impl fmt::Display for RconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RconError::ConnectionRefused(address) => write!(
+                f,
+                "Connection to {} was refused. Is the server online with RCON enabled?",
+                address
+            ),
+            RconError::Dns(address) => write!(f, "Could not resolve {}", address),
+            RconError::Tls(reason) => write!(f, "TLS error: {}", reason),
+            RconError::Timeout => write!(f, "Timed out waiting for the server"),
+            RconError::AuthRejected => write!(f, "The server rejected the password"),
+            RconError::CommandTooLong { length, max } => write!(
+                f,
+                "Command is {} bytes long, the maximum is {} bytes",
+                length, max
+            ),
+            RconError::MalformedPacket(reason) => {
+                write!(f, "Received a malformed packet: {}", reason)
+            }
+            RconError::Protocol(reason) => {
+                write!(f, "The server broke the RCON protocol: {}", reason)
+            }
+            RconError::InvalidUtf8 => write!(f, "Received a response which isn't valid UTF-8"),
+            RconError::Disconnected => write!(f, "The server closed the connection"),
+            RconError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RconError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RconError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+///Sorts the io errors we can get from a socket into the variants above. Anything unrecognised is kept as is.
+impl From<io::Error> for RconError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => RconError::Disconnected,
+            io::ErrorKind::TimedOut => RconError::Timeout,
+            _ => RconError::Io(e),
+        }
+    }
+}
@@ -1,11 +1,18 @@
 use crossterm::style::{Attribute, ContentStyle, Stylize};
 
+use crate::games::{Classification, ResponseError};
+
 pub struct Generic;
 
 impl Generic {
     pub fn get_commands() -> Vec<String> {
         return vec![];
     }
+
+    //Nothing is known about the commands, so nothing can be split safely
+    pub fn split_command(_command: &str, _limit: usize) -> Option<Vec<String>> {
+        return None;
+    }
 }
 
 pub fn get_output(response: &str) -> Vec<(String, ContentStyle)> {
@@ -14,3 +21,12 @@ pub fn get_output(response: &str) -> Vec<(String, ContentStyle)> {
         ContentStyle::new().attribute(Attribute::Reset),
     )];
 }
+
+//Without knowing the game, the only error that can be told apart is the Source engine's unknown command reply, which
+// several other games copy
+pub fn classify(response: &str) -> Classification {
+    if response.trim_start().starts_with("Unknown command") {
+        return Classification::new("UnknownCommand", Some(ResponseError::InvalidCommand));
+    }
+    return Classification::new("Default", None);
+}
@@ -3,10 +3,13 @@ use std::slice::Iter;
 
 use crossterm::style::{Attribute, ContentStyle, Stylize};
 
-use crate::games::Response;
+use crate::games::{split_message, Classification, Field, Response, ResponseError};
 pub struct Minecraft;
 
 impl Minecraft {
+    //The server reads packets into a 1460 byte buffer and silently drops anything larger. Minus the header that leaves 1446.
+    pub const COMMAND_LIMIT: usize = 1446;
+
     //I should probably find a way to load this from a file or something.
     /* const COMMANDS: [&'static str; 82] = [
 
@@ -101,9 +104,26 @@ impl Minecraft {
         .map(|s| return s.to_string())
         .collect();
     }
+
+    //Only chat commands can be split without changing what they do. Anything with JSON or NBT (tellraw, data merge) can't.
+    pub fn split_command(command: &str, limit: usize) -> Option<Vec<String>> {
+        let (name, args) = command.trim_start().split_once(' ')?;
+        let name = name.trim_start_matches('/');
+        match name {
+            "say" | "me" | "teammsg" | "tm" => {
+                return split_message(&(name.to_string() + " "), args, limit)
+            }
+            "tell" | "msg" | "w" => {
+                let (target, message) = args.trim_start().split_once(' ')?;
+                let prefix = name.to_string() + " " + target + " ";
+                return split_message(&prefix, message, limit);
+            }
+            _ => return None,
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum MinecraftResponse {
     UnknownCommand,
     PlayerNotFound,
@@ -119,6 +139,83 @@ pub enum MinecraftResponse {
     Default,
 }
 
+impl MinecraftResponse {
+    pub fn error(&self) -> Option<ResponseError> {
+        return match self {
+            MinecraftResponse::UnknownCommand
+            | MinecraftResponse::IncorrectArg
+            | MinecraftResponse::InvalidInteger
+            | MinecraftResponse::ExpectedInteger
+            | MinecraftResponse::IntegerMin => Some(ResponseError::InvalidCommand),
+            MinecraftResponse::PlayerNotFound
+            | MinecraftResponse::UnknownItem
+            | MinecraftResponse::NoElement => Some(ResponseError::NotFound),
+            _ => None,
+        };
+    }
+
+    //Classifies the response and pulls out the numbers and names the formatting already knows how to find
+    pub fn classify(response: &str) -> Classification {
+        let res_type = Self::from_response_str(response);
+        let mut classification = Classification::new(&format!("{:?}", res_type), res_type.error());
+
+        let captures = Regex::new(Self::get_id_string(&res_type))
+            .unwrap()
+            .captures(response);
+        let number = |i: usize| {
+            return captures
+                .as_ref()
+                .and_then(|c| c.get(i))
+                .and_then(|m| m.as_str().parse::<i64>().ok())
+                .map(Field::Number);
+        };
+        let mut fields = Vec::<(&str, Option<Field>)>::new();
+
+        match res_type {
+            MinecraftResponse::ListPlayers => {
+                let players = response
+                    .split_once(':')
+                    .map(|(_, names)| names)
+                    .unwrap_or("")
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect();
+                fields.push(("online", number(1)));
+                fields.push(("max", number(2)));
+                fields.push(("players", Some(Field::List(players))));
+            }
+            MinecraftResponse::ListBans => fields.push(("bans", number(1))),
+            MinecraftResponse::IntegerMin => fields.push((
+                "found",
+                number(1).map(|n| match n {
+                    Field::Number(n) => Field::Number(-n),
+                    other => other,
+                }),
+            )),
+            MinecraftResponse::UnknownItem
+            | MinecraftResponse::InvalidInteger
+            | MinecraftResponse::NoElement => {
+                //These quote the offending value, e.g. Unknown item 'minecraft:stne'
+                let quoted = response
+                    .split('\'')
+                    .nth(1)
+                    .map(|v| Field::Text(v.to_string()));
+                fields.push(("value", quoted));
+            }
+            _ => {}
+        }
+
+        for (name, value) in fields {
+            if let Some(value) = value {
+                classification.fields.insert(name.to_string(), value);
+            }
+        }
+
+        return classification;
+    }
+}
+
 impl Response<MinecraftResponse> for MinecraftResponse {
     //Returns the most identifying part of the response. Might need to get a little more complicated with it, for example the list command identifier is very
     //short. Not sure if that's a problem.
@@ -129,10 +226,11 @@ impl Response<MinecraftResponse> for MinecraftResponse {
             }
             MinecraftResponse::PlayerNotFound => "No player was found",
             //Handles both the list and banlist case, as their syntax is very similar
-            MinecraftResponse::ListPlayers => r"There are (\d+) of a max of (\d+}) players online",
+            MinecraftResponse::ListPlayers => r"There are (\d+) of a max of (\d+) players online",
             MinecraftResponse::IntegerMin => r"Integer must not be less than 1, found -(\d+)",
-            MinecraftResponse::Help => "/",
-            MinecraftResponse::ListBans => r"There are (\d+}) ban\(s\)",
+            //help lists commands as /advancement ..., anything else with a slash in it is not help
+            MinecraftResponse::Help => "^/",
+            MinecraftResponse::ListBans => r"There are (\d+) ban\(s\)",
             MinecraftResponse::IncorrectArg => "Incorrect argument for command",
             MinecraftResponse::UnknownItem => "Unknown item '",
             MinecraftResponse::InvalidInteger => "Invalid integer '",
@@ -150,13 +248,14 @@ impl Response<MinecraftResponse> for MinecraftResponse {
             MinecraftResponse::PlayerNotFound,
             MinecraftResponse::ListPlayers,
             MinecraftResponse::ListBans,
-            MinecraftResponse::Help,
             MinecraftResponse::IncorrectArg,
             MinecraftResponse::UnknownItem,
             MinecraftResponse::InvalidInteger,
             MinecraftResponse::IntegerMin,
             MinecraftResponse::NoElement,
             MinecraftResponse::ExpectedInteger,
+            //Last, so an error that quotes a command never counts as help
+            MinecraftResponse::Help,
         ]
         .iter()
     }
@@ -176,7 +275,7 @@ impl Response<MinecraftResponse> for MinecraftResponse {
 
     //Huge match statement which contains the formatting for all the responses we want to modify formatting for.
     fn get_output(response: &str) -> Vec<(String, ContentStyle)> {
-        let res_type = Self::from_response_str(&response);
+        let res_type = Self::from_response_str(response);
         let id_str = MinecraftResponse::get_id_string(&res_type);
         match res_type {
             MinecraftResponse::UnknownCommand => {
@@ -211,10 +310,13 @@ impl Response<MinecraftResponse> for MinecraftResponse {
             }
             MinecraftResponse::ListPlayers => {
                 let mut lines = Vec::<(String, ContentStyle)>::new();
-                let sections = response.split_once(":").unwrap();
+                //Without any players there may be nothing after the count, not even the colon
+                let Some(sections) = response.split_once(":") else {
+                    return vec![(response.to_string(), ContentStyle::new())];
+                };
                 lines.push((sections.0.to_string(), ContentStyle::new().bold()));
 
-                if sections.1.trim().len() > 0 {
+                if !sections.1.trim().is_empty() {
                     lines.push((
                         sections.1.trim().to_string(),
                         ContentStyle::new().attribute(Attribute::Reset),
@@ -242,12 +344,14 @@ impl Response<MinecraftResponse> for MinecraftResponse {
             MinecraftResponse::ListBans => {
                 let mut lines = Vec::<(String, ContentStyle)>::new();
 
-                let sections = response.split_once(":").unwrap();
+                let Some(sections) = response.split_once(":") else {
+                    return vec![(response.to_string(), ContentStyle::new())];
+                };
                 //List or banlist with player case
 
                 lines.push((sections.0.to_string(), ContentStyle::new().bold()));
 
-                if sections.1.trim().len() > 0 {
+                if !sections.1.trim().is_empty() {
                     lines.push((
                         sections.1.trim().to_string(),
                         ContentStyle::new().attribute(Attribute::Reset),
@@ -341,3 +445,104 @@ impl Response<MinecraftResponse> for MinecraftResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_with_a_slash_are_not_help() {
+        let response = "Expected integer at position 9: ...e 1/2<--[HERE]";
+        let classification = MinecraftResponse::classify(response);
+        assert_eq!(classification.kind, "ExpectedInteger");
+        assert_eq!(classification.error, Some(ResponseError::InvalidCommand));
+
+        let response = "Incorrect argument for command...ect @s/<--[HERE]";
+        assert_eq!(
+            MinecraftResponse::from_response_str(response),
+            MinecraftResponse::IncorrectArg
+        );
+    }
+
+    fn players(names: &[&str]) -> Field {
+        return Field::List(names.iter().map(|name| name.to_string()).collect());
+    }
+
+    #[test]
+    fn classifies_list_with_players() {
+        let classification =
+            MinecraftResponse::classify("There are 2 of a max of 20 players online: Steve, Alex");
+        assert_eq!(classification.kind, "ListPlayers");
+        assert_eq!(classification.error, None);
+        assert_eq!(classification.fields["online"], Field::Number(2));
+        assert_eq!(classification.fields["max"], Field::Number(20));
+        assert_eq!(
+            classification.fields["players"],
+            players(&["Steve", "Alex"])
+        );
+    }
+
+    #[test]
+    fn classifies_list_without_players() {
+        let classification =
+            MinecraftResponse::classify("There are 0 of a max of 20 players online: ");
+        assert_eq!(classification.fields["online"], Field::Number(0));
+        assert_eq!(classification.fields["max"], Field::Number(20));
+        assert_eq!(classification.fields["players"], players(&[]));
+
+        //some servers leave out the colon as well
+        let classification =
+            MinecraftResponse::classify("There are 0 of a max of 20 players online");
+        assert_eq!(classification.fields["players"], players(&[]));
+    }
+
+    #[test]
+    fn classifies_bans() {
+        let classification = MinecraftResponse::classify("There are 3 ban(s):");
+        assert_eq!(classification.kind, "ListBans");
+        assert_eq!(classification.fields["bans"], Field::Number(3));
+    }
+
+    #[test]
+    fn classifies_an_unknown_item() {
+        let classification =
+            MinecraftResponse::classify("Unknown item 'minecraft:stne'...stne<--[HERE]");
+        assert_eq!(classification.kind, "UnknownItem");
+        assert_eq!(classification.error, Some(ResponseError::NotFound));
+        assert_eq!(
+            classification.fields["value"],
+            Field::Text("minecraft:stne".to_string())
+        );
+    }
+
+    #[test]
+    fn classifies_a_negative_integer() {
+        let classification =
+            MinecraftResponse::classify("Integer must not be less than 1, found -5");
+        assert_eq!(classification.error, Some(ResponseError::InvalidCommand));
+        assert_eq!(classification.fields["found"], Field::Number(-5));
+    }
+
+    #[test]
+    fn formats_counts_without_a_colon() {
+        let response = "There are 1 of a max of 20 players online";
+        assert_eq!(
+            MinecraftResponse::get_output(response),
+            vec![(response.to_string(), ContentStyle::new())]
+        );
+
+        let response = "There are 0 ban(s)";
+        assert_eq!(
+            MinecraftResponse::get_output(response),
+            vec![(response.to_string(), ContentStyle::new())]
+        );
+    }
+
+    #[test]
+    fn classifies_help() {
+        let classification =
+            MinecraftResponse::classify("/advancement (grant|revoke)/ban <targets>");
+        assert_eq!(classification.kind, "Help");
+        assert_eq!(classification.error, None);
+    }
+}
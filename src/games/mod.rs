@@ -1,18 +1,21 @@
 mod generic;
-mod minecraft;
+pub mod minecraft;
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use std::slice::Iter;
 
 use crossterm::style::ContentStyle;
+use serde::{Deserialize, Serialize};
 
 use minecraft::{Minecraft, MinecraftResponse};
 
 use self::generic::Generic;
 
-///Game selection enum. Used in GameMapper and for command line arguments.
-#[derive(Clone, PartialEq)]
+///Game selection enum. Used in GameMapper, for command line arguments and in the config file.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Game {
     MINECRAFT,
     GENERIC,
@@ -59,6 +62,97 @@ pub trait Response<T> {
     fn get_output(response: &str) -> Vec<(String, ContentStyle)>;
 }
 
+///What a response means, as far as the game's response parser can tell
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Classification {
+    ///Name of the kind of response, for Minecraft the MinecraftResponse variant
+    pub kind: String,
+    ///Set when the server is reporting that the command failed
+    pub error: Option<ResponseError>,
+    ///Values parsed out of the response, for example the player counts of `list`
+    pub fields: BTreeMap<String, Field>,
+}
+
+impl Classification {
+    pub fn new(kind: &str, error: Option<ResponseError>) -> Classification {
+        return Classification {
+            kind: kind.to_string(),
+            error,
+            fields: BTreeMap::new(),
+        };
+    }
+}
+
+///The ways a command can fail on the server's side
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseError {
+    ///The command doesn't exist or its arguments are wrong
+    InvalidCommand,
+    ///Something the command refers to, like a player or an item, doesn't exist
+    NotFound,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Field {
+    Number(i64),
+    Text(String),
+    List(Vec<String>),
+}
+
+///Splits `message` at word boundaries into commands starting with `prefix`, none of them longer than `limit` bytes.
+/// Words that don't fit on their own are broken up. Returns `None` if the prefix leaves no room for the message.
+pub(crate) fn split_message(prefix: &str, message: &str, limit: usize) -> Option<Vec<String>> {
+    let budget = limit.checked_sub(prefix.len()).filter(|b| *b > 0)?;
+    let mut chunks = Vec::<String>::new();
+    let mut current = String::new();
+
+    for word in message.split_whitespace() {
+        let needed = if current.is_empty() {
+            word.len()
+        } else {
+            current.len() + 1 + word.len()
+        };
+        if needed <= budget {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        for c in word.chars() {
+            if c.len_utf8() > budget {
+                return None;
+            }
+            if current.len() + c.len_utf8() > budget {
+                chunks.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    //Nothing but whitespace, sending a bare prefix wouldn't be what was typed either
+    if chunks.is_empty() {
+        return None;
+    }
+
+    return Some(
+        chunks
+            .into_iter()
+            .map(|chunk| String::from(prefix) + &chunk)
+            .collect(),
+    );
+}
+
 ///Returns function references for getting the command list and getting formatted responses based on the currently selected game
 pub struct GameMapper;
 
@@ -70,6 +164,31 @@ impl GameMapper {
         }
     }
 
+    ///Returns the longest command in bytes the game's server accepts, if it's lower than what the protocol allows
+    pub fn get_command_limit(game: &Game) -> Option<usize> {
+        match game {
+            Game::MINECRAFT => return Some(Minecraft::COMMAND_LIMIT),
+            Game::GENERIC => return None,
+        }
+    }
+
+    ///Returns a function which splits a command that's over the limit into several shorter ones with the same effect,
+    /// or None if that's not possible for this command
+    pub fn get_split_fn(game: &Game) -> &'static dyn Fn(&str, usize) -> Option<Vec<String>> {
+        match game {
+            Game::MINECRAFT => return &Minecraft::split_command,
+            Game::GENERIC => return &Generic::split_command,
+        }
+    }
+
+    ///Returns a function which works out what kind of response the server sent and whether it's an error
+    pub fn get_classify_fn(game: &Game) -> &'static dyn Fn(&str) -> Classification {
+        match game {
+            Game::MINECRAFT => return &MinecraftResponse::classify,
+            Game::GENERIC => return &generic::classify,
+        }
+    }
+
     pub fn get_response_fn(game: &Game) -> &'static dyn Fn(&str) -> Vec<(String, ContentStyle)> {
         match game {
             Game::MINECRAFT => return &MinecraftResponse::get_output,
@@ -77,3 +196,37 @@ impl GameMapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_words_within_the_limit() {
+        let parts = split_message("say ", "one two three four", 13).unwrap();
+        assert_eq!(parts, ["say one two", "say three", "say four"]);
+        assert!(parts.iter().all(|p| p.len() <= 13));
+    }
+
+    #[test]
+    fn breaks_up_words_longer_than_the_limit() {
+        let parts = split_message("say ", "abcdefgh", 8).unwrap();
+        assert_eq!(parts, ["say abcd", "say efgh"]);
+    }
+
+    #[test]
+    fn never_sends_an_empty_chunk() {
+        //two byte characters with room for one each
+        let parts = split_message("say ", "ééé", 6).unwrap();
+        assert_eq!(parts, ["say é", "say é", "say é"]);
+        assert_eq!(split_message("say ", "   ", 10), None);
+    }
+
+    #[test]
+    fn gives_up_when_the_prefix_leaves_no_room() {
+        assert_eq!(split_message("tell somebody ", "hi", 14), None);
+        assert_eq!(split_message("tell somebody ", "hi", 5), None);
+        //a character wider than what's left
+        assert_eq!(split_message("say ", "é", 5), None);
+    }
+}
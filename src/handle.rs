@@ -0,0 +1,96 @@
+use crossterm::style::ContentStyle;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::client::RconClient;
+use crate::error::RconError;
+use crate::games::{Game, GameMapper};
+
+//How many requests can wait for the connection before senders have to wait as well
+const QUEUE_SIZE: usize = 64;
+
+enum Request {
+    Command {
+        command: String,
+        reply: oneshot::Sender<Result<String, RconError>>,
+    },
+    Reconnect {
+        reply: oneshot::Sender<Result<(), RconError>>,
+    },
+}
+
+///A cloneable handle to an [`RconClient`] owned by a background task. Lets a shell, a poller and a metrics task share
+/// one authenticated connection. Requests are queued and sent one at a time, as RCON responses can only be told apart
+/// by id and multi-packet responses must not interleave. Created with [`RconClient::spawn`].
+///
+/// The task ends, closing the connection, once every handle has been dropped.
+#[derive(Clone)]
+pub struct RconHandle {
+    requests: mpsc::Sender<Request>,
+    game: Game,
+}
+
+impl RconClient {
+    ///Moves the client onto a background task and returns a handle to it. Has to be called within a tokio runtime.
+    pub fn spawn(self) -> RconHandle {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let game = self.game().clone();
+        tokio::spawn(run(self, rx));
+
+        return RconHandle { requests: tx, game };
+    }
+}
+
+async fn run(mut client: RconClient, mut requests: mpsc::Receiver<Request>) {
+    while let Some(request) = requests.recv().await {
+        //A dropped receiver only means the caller stopped waiting, e.g. because of its own timeout
+        match request {
+            Request::Command { command, reply } => {
+                let _ = reply.send(client.send_command(&command).await);
+            }
+            Request::Reconnect { reply } => {
+                let _ = reply.send(client.reconnect().await);
+            }
+        }
+    }
+}
+
+impl RconHandle {
+    ///Queues a command and waits for its full response. See [`RconClient::send_command`].
+    pub async fn send_command(&self, command: &str) -> Result<String, RconError> {
+        let (reply, response) = oneshot::channel();
+        self.request(Request::Command {
+            command: String::from(command),
+            reply,
+        })
+        .await?;
+
+        return response.await.map_err(|_| RconError::Disconnected)?;
+    }
+
+    ///Reconnects and logs in again once every request queued before this one is done. See [`RconClient::reconnect`].
+    pub async fn reconnect(&self) -> Result<(), RconError> {
+        let (reply, response) = oneshot::channel();
+        self.request(Request::Reconnect { reply }).await?;
+
+        return response.await.map_err(|_| RconError::Disconnected)?;
+    }
+
+    ///The game profile responses are formatted with
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    ///Splits a response into lines styled for the client's game
+    pub fn format_response(&self, response: &str) -> Vec<(String, ContentStyle)> {
+        return (GameMapper::get_response_fn(&self.game))(response);
+    }
+
+    //The task only goes away if it panicked, which leaves nothing to talk to
+    async fn request(&self, request: Request) -> Result<(), RconError> {
+        return self
+            .requests
+            .send(request)
+            .await
+            .map_err(|_| RconError::Disconnected);
+    }
+}
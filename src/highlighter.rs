@@ -1,7 +1,9 @@
-use nu_ansi_term::{Color, Style};
+use nu_ansi_term::Style;
 use reedline::{Highlighter, StyledText};
 
 use crate::games::Game;
+use crate::meta;
+use crate::theme::Theme;
 
 pub struct RCONHighlighter {
     commands: Vec<String>,
@@ -12,12 +14,20 @@ pub struct RCONHighlighter {
 }
 
 impl Highlighter for RCONHighlighter {
-    fn highlight(&self, line: &str, cursor: usize) -> reedline::StyledText {
+    fn highlight(&self, line: &str, _cursor: usize) -> reedline::StyledText {
         let mut styled_text = StyledText::new();
 
         let words: Vec<&str> = line.split_inclusive(" ").collect();
         for (i, word) in words.iter().enumerate() {
-            if self.commands.contains(&word.trim().to_string()) && i == 0 {
+            //meta-commands are the shell's own, so they're known whatever the game
+            let meta_name = word.trim().strip_prefix(':').filter(|_| i == 0);
+            if let Some(name) = meta_name {
+                let style = match meta::find(name) {
+                    Some(_) => self.command_style,
+                    None => self.nomatch_style,
+                };
+                styled_text.push((style, word.to_string()));
+            } else if self.commands.contains(&word.trim().to_string()) && i == 0 {
                 styled_text.push((self.command_style, word.to_string()));
             } else if i == 0 && !self.is_generic {
                 styled_text.push((self.nomatch_style, word.to_string()));
@@ -31,13 +41,13 @@ impl Highlighter for RCONHighlighter {
 }
 
 impl RCONHighlighter {
-    pub fn new(commands: Vec<String>, game: Game) -> RCONHighlighter {
+    pub fn new(commands: Vec<String>, game: Game, theme: Theme) -> RCONHighlighter {
         RCONHighlighter {
             commands,
             is_generic: game == Game::GENERIC,
-            command_style: Style::new().fg(Color::LightYellow),
-            neutral_style: Style::new().fg(Color::LightGray),
-            nomatch_style: Style::new().fg(Color::Red),
+            command_style: theme.command_style(),
+            neutral_style: theme.neutral_style(),
+            nomatch_style: theme.nomatch_style(),
         }
     }
 }
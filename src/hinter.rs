@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use nu_ansi_term::Style;
+use reedline::{Hinter, History, SearchQuery};
+
+use crate::theme::Theme;
+
+///Commands whose last response was an error, shared between the shell, which classifies the responses, and the hinter.
+/// reedline wants its hinter to be `Send`, hence the mutex.
+pub type FailedCommands = Arc<Mutex<HashSet<String>>>;
+
+///Suggests the rest of the line from the server's history, fish style. Of the commands starting with what has been
+/// typed, the most recent one which didn't get an error back is suggested. A command that failed is only suggested
+/// when nothing else matches.
+pub struct RCONHinter {
+    style: Style,
+    current_hint: String,
+    failed: FailedCommands,
+}
+
+impl Hinter for RCONHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        _pos: usize,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.current_hint = match line.trim().is_empty() {
+            true => String::new(),
+            false => self.find_hint(line, history),
+        };
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            return self.style.paint(&self.current_hint).to_string();
+        }
+        return self.current_hint.clone();
+    }
+
+    fn complete_hint(&self) -> String {
+        return self.current_hint.clone();
+    }
+
+    fn next_hint_token(&self) -> String {
+        //Leading whitespace belongs to the token, otherwise accepting a word at a time would never get past a space
+        let start = self
+            .current_hint
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(self.current_hint.len());
+        let end = self.current_hint[start..]
+            .find(char::is_whitespace)
+            .map_or(self.current_hint.len(), |i| start + i);
+
+        return self.current_hint[..end].to_string();
+    }
+}
+
+impl RCONHinter {
+    pub fn new(theme: Theme, failed: FailedCommands) -> RCONHinter {
+        RCONHinter {
+            style: theme.hint_style(),
+            current_hint: String::new(),
+            failed,
+        }
+    }
+
+    fn find_hint(&self, line: &str, history: &dyn History) -> String {
+        let query = SearchQuery {
+            limit: None,
+            ..SearchQuery::last_with_prefix(line.to_string(), history.session())
+        };
+        let Ok(entries) = history.search(query) else {
+            return String::new();
+        };
+        let failed = self.failed.lock().unwrap_or_else(|e| e.into_inner());
+
+        //Entries come most recent first
+        let mut fallback = None;
+        for entry in entries.iter().filter(|e| e.command_line.len() > line.len()) {
+            if !failed.contains(&entry.command_line) {
+                return entry.command_line[line.len()..].to_string();
+            }
+            fallback.get_or_insert(&entry.command_line);
+        }
+
+        return fallback.map_or_else(String::new, |c| c[line.len()..].to_string());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use reedline::FileBackedHistory;
+
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+///Where and how the shell keeps its command history. Each server gets its own file in `dir`, so searching every file in
+/// it searches the history of all servers.
+#[derive(Clone, Debug)]
+pub struct HistoryOptions {
+    pub dir: PathBuf,
+    ///Names the server's file, usually the profile name or the address
+    pub key: String,
+    ///Most commands kept per server
+    pub size: usize,
+    ///Only keep the latest use of each command
+    pub dedup: bool,
+}
+
+impl HistoryOptions {
+    pub fn new(key: &str) -> Option<HistoryOptions> {
+        return Some(HistoryOptions {
+            dir: Self::default_dir()?,
+            key: key.to_string(),
+            size: DEFAULT_HISTORY_SIZE,
+            dedup: true,
+        });
+    }
+
+    ///`$XDG_DATA_HOME/frcon/history`, falling back to `~/.local/share/frcon/history`
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        };
+
+        return Some(base.join("frcon").join("history"));
+    }
+
+    ///The server's history file. Characters that don't belong in a file name, like the colon between address and port,
+    /// are replaced.
+    pub fn path(&self) -> PathBuf {
+        return self.dir.join(self.file_name() + ".txt");
+    }
+
+    ///Commands whose last response was an error, kept next to the history so suggestions can pass over them
+    pub fn failed_path(&self) -> PathBuf {
+        return self.dir.join(self.file_name() + ".failed");
+    }
+
+    fn file_name(&self) -> String {
+        return self
+            .key
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+    }
+
+    ///Opens the server's history, deduplicating the file first if asked to. reedline itself only skips a command that's
+    /// the same as the one before it.
+    pub fn open(&self) -> io::Result<FileBackedHistory> {
+        self.remove_duplicates()?;
+
+        return FileBackedHistory::with_file(self.size, self.path())
+            .map_err(|e| io::Error::other(e.to_string()));
+    }
+
+    ///Drops all but the latest use of each command from the file, if asked to. Returns whether anything was dropped.
+    pub fn remove_duplicates(&self) -> io::Result<bool> {
+        if !self.dedup {
+            return Ok(false);
+        }
+        return dedup_file(&self.path());
+    }
+
+    ///Commands that got an error the last time they were sent to this server
+    pub fn failed(&self) -> io::Result<HashSet<String>> {
+        return Ok(read_entries(&self.failed_path())?.into_iter().collect());
+    }
+
+    pub fn save_failed(&self, failed: &HashSet<String>) -> io::Result<()> {
+        let mut entries: Vec<&String> = failed.iter().collect();
+        entries.sort();
+
+        let contents: String = entries
+            .iter()
+            .map(|entry| entry.to_string() + "\n")
+            .collect();
+        std::fs::create_dir_all(&self.dir)?;
+        return std::fs::write(self.failed_path(), contents);
+    }
+
+    ///Every command in this server's history, oldest first
+    pub fn entries(&self) -> io::Result<Vec<String>> {
+        return read_entries(&self.path());
+    }
+
+    ///Commands containing `text` from the history of every server, as (server, command) pairs. Each server's
+    /// commands are listed most recent first.
+    pub fn search_all(&self, text: &str) -> io::Result<Vec<(String, String)>> {
+        let text = text.to_lowercase();
+        let mut found = Vec::new();
+
+        let mut files: Vec<PathBuf> = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(found),
+            Err(e) => return Err(e),
+        };
+        files.sort();
+
+        for file in files {
+            let server = file
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            //the file of a server without a shell open may not be deduplicated yet
+            let mut seen = HashSet::new();
+            for entry in read_entries(&file)?.into_iter().rev() {
+                if entry.to_lowercase().contains(&text) && seen.insert(entry.clone()) {
+                    found.push((server.clone(), entry));
+                }
+            }
+        }
+
+        return Ok(found);
+    }
+}
+
+//reedline writes one command per line, with newlines inside a command escaped, so lines can be handled as they are
+fn read_entries(path: &Path) -> io::Result<Vec<String>> {
+    return match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.lines().map(String::from).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    };
+}
+
+//Keeps the last use of each command, so the order still reflects what was used most recently
+fn dedup_file(path: &Path) -> io::Result<bool> {
+    let entries = read_entries(path)?;
+    let mut seen = HashSet::new();
+    let mut kept: Vec<&String> = entries
+        .iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.as_str()))
+        .collect();
+
+    if kept.len() == entries.len() {
+        return Ok(false);
+    }
+
+    kept.reverse();
+    let contents: String = kept.iter().map(|entry| entry.to_string() + "\n").collect();
+    std::fs::write(path, contents)?;
+    return Ok(true);
+}
@@ -0,0 +1,31 @@
+//!An RCON client library, and the shell behind the `frcon` binary.
+//!
+//! [`RconClient`] is the main entry point. It handles connecting, logging in, splitting multi-packet responses and
+//! formatting them for a [`Game`]. The lower level [`rcon::RCONConnection`] and the interactive [`shell::RCONShell`]
+//! are exposed as well. [`RconHandle`] shares one connection between several tasks,
+//! and [`blocking::RCONConnection`] works without a tokio runtime.
+
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
+
+pub mod blocking;
+pub mod client;
+pub mod completer;
+pub mod config;
+pub mod error;
+pub mod games;
+pub mod handle;
+pub mod highlighter;
+pub mod hinter;
+pub mod history;
+pub mod meta;
+pub mod rcon;
+pub mod shell;
+pub mod theme;
+pub mod transcript;
+pub mod transport;
+
+pub use client::{RconClient, RconClientBuilder};
+pub use error::RconError;
+pub use games::Game;
+pub use handle::RconHandle;
+pub use transport::TlsOptions;
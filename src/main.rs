@@ -1,130 +1,795 @@
-use crossterm::style::{ContentStyle, StyledContent, Stylize};
-use games::Game;
-use std::{str::FromStr, thread::sleep, time::Duration};
+#![allow(clippy::needless_return)]
+
+mod cli;
+mod completions;
+mod script;
+mod watch;
 
-use argh::FromArgs;
-use shell::RCONShell;
+use crossterm::style::{ContentStyle, StyledContent, Stylize};
+use frcon::games::{Classification, Game, GameMapper, ResponseError};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-mod games;
-mod highlighter;
-mod rcon;
-mod shell;
+use cli::{
+    Cli, Command, ConnectArgs, LegacyArgs, OutputFormat, ServersCommand, ServersSubcommand,
+    WatchCommand, SUBCOMMANDS,
+};
+use frcon::config::{Config, Profile};
+use frcon::history::HistoryOptions;
+use frcon::shell::{RCONShell, ShellOptions};
+use frcon::transcript::{LogFormat, SessionLog};
+use frcon::{RconClient, RconError, TlsOptions};
+use script::Step;
+use serde::Serialize;
+use std::io::{Read, Write};
+use zeroize::Zeroizing;
 
 const VERSION: &str = "1.2.0";
 
-#[derive(FromArgs)]
-#[argh(description = "Minecraft RCON Implementation for Rust")]
-struct Args {
-    #[argh(option, description = "address of the server", short = 'a')]
-    address: String,
-
-    #[argh(
-        option,
-        description = "RCON port of the server",
-        default = "25575",
-        short = 'p'
-    )]
-    port: u16,
-
-    #[argh(option, description = "RCON password", short = 'P')]
-    password: String,
-
-    #[argh(
-        option,
-        description = "enables game specific prompt features (minecraft)",
-        short = 'g',
-        default = "Game::from_str(\"generic\").unwrap()"
-    )]
-    game: games::Game,
-
-    #[argh(
-        positional,
-        description = "will be executed and shell mode will not be entered"
-    )]
-    commands: Vec<String>,
-
-    #[argh(
-        option,
-        description = "seconds to wait between each passed command",
-        short = 'w'
-    )]
-    wait: Option<u64>,
+const PASSWORD_ENV: &str = "FRCON_PASSWORD";
 
-    #[argh(
-        switch,
-        description = "disables output printing for passed commands",
-        short = 's'
-    )]
-    silent: Option<bool>,
+const DEFAULT_PORT: u16 = 25575;
 
-    #[argh(switch, description = "prints version information", short = 'v')]
-    version: Option<bool>,
-}
+///Exit code for a response that didn't match its @expect
+const EXPECT_FAILED: i32 = 10;
+
+///Shortest interval watch runs at, anything shorter would hammer the server
+const MIN_WATCH_INTERVAL: Duration = Duration::from_millis(100);
 
 #[tokio::main]
 async fn main() {
-    let args: Args = argh::from_env();
+    let subcommand = std::env::args()
+        .nth(1)
+        .filter(|a| a == "help" || SUBCOMMANDS.contains(&a.as_str()));
+
+    let code = match subcommand {
+        Some(_) => {
+            let mut cli: Cli = parse_args(hide_dashes(std::env::args().collect()));
+            restore_dashes(&mut cli);
+            match cli.command {
+                Command::Shell(cmd) => {
+                    let mut args = cmd.connect_args();
+                    if cmd.profile.is_some() {
+                        args.server = cmd.profile.clone();
+                    }
+                    run_shell(&args, cmd.log_session.clone(), cmd.log_format).await
+                }
+                Command::Exec(cmd) => {
+                    let mut args = cmd.connect_args();
+                    let mut commands = cmd.commands.clone();
+                    if args.server.is_none() && args.address.is_none() && !commands.is_empty() {
+                        args.server = Some(commands.remove(0));
+                    }
+                    match exec_steps(&cmd.file, &commands, cmd.wait) {
+                        Ok(steps) => {
+                            let options = ExecOptions {
+                                fail_fast: cmd.fail_fast,
+                                fail_on_error: !cmd.no_fail_on_error,
+                                silent: cmd.silent.is_some(),
+                                output: cmd.output,
+                            };
+                            run_exec(&args, &steps, options).await
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e.as_str().red());
+                            1
+                        }
+                    }
+                }
+                Command::Watch(cmd) => {
+                    let mut args = cmd.connect_args();
+                    let mut words = cmd.command.clone();
+                    if args.server.is_none() && args.address.is_none() && !words.is_empty() {
+                        args.server = Some(words.remove(0));
+                    }
+                    if words.is_empty() {
+                        eprintln!("{}", "No command given.".red());
+                        1
+                    } else {
+                        run_watch(&args, &words.join(" "), &cmd).await
+                    }
+                }
+                Command::Servers(cmd) => run_servers(cmd).await,
+                Command::Completions(cmd) => {
+                    print!("{}", completions::script(&cmd.shell));
+                    0
+                }
+            }
+        }
+        None => run_legacy(argh::from_env()).await,
+    };
+
+    std::process::exit(code);
+}
 
-    if args.version == Some(true) {
+//Stands in for a lone `-` while argh parses. Arguments can't contain a NUL, so it never clashes with a real one.
+const DASH: &str = "\0-";
+
+///argh reads a lone `-` as an unknown option, so before `--` it's swapped for [`DASH`] and swapped back by
+/// [`restore_dashes`] once parsed, leaving it where it was. The value of -f is left alone.
+fn hide_dashes(mut argv: Vec<String>) -> Vec<String> {
+    let end = argv.iter().position(|a| a == "--").unwrap_or(argv.len());
+    for i in 1..end {
+        if argv[i] == "-" && !matches!(argv[i - 1].as_str(), "-f" | "--file") {
+            argv[i] = String::from(DASH);
+        }
+    }
+    return argv;
+}
+
+fn restore_dashes(cli: &mut Cli) {
+    let positionals = match &mut cli.command {
+        Command::Shell(cmd) => cmd.profile.iter_mut().collect(),
+        Command::Exec(cmd) => cmd.commands.iter_mut().collect(),
+        Command::Watch(cmd) => cmd.command.iter_mut().collect(),
+        _ => Vec::new(),
+    };
+    for arg in positionals.into_iter().filter(|a| *a == DASH) {
+        *arg = String::from("-");
+    }
+}
+
+///The same as argh::from_env, for arguments that have been changed first
+fn parse_args<T: argh::TopLevelCommand>(argv: Vec<String>) -> T {
+    let strs: Vec<&str> = argv.iter().skip(1).map(|s| s.as_str()).collect();
+
+    return T::from_args(&["frcon"], &strs).unwrap_or_else(|early_exit| {
+        std::process::exit(match early_exit.status {
+            Ok(()) => {
+                println!("{}", early_exit.output);
+                0
+            }
+            Err(()) => {
+                eprintln!(
+                    "{}\nRun frcon --help for more information.",
+                    early_exit.output
+                );
+                1
+            }
+        })
+    });
+}
+
+///The flag based command line from before subcommands existed
+async fn run_legacy(legacy: LegacyArgs) -> i32 {
+    if legacy.version == Some(true) {
         print_version();
-        std::process::exit(0);
+        return 0;
     }
 
-    // Used as an ID for the RCON protocol
-    let pid = std::process::id();
+    let silent = legacy.silent.is_some();
+    let mut args = legacy.connect_args();
+    let mut commands = legacy.commands.clone();
 
-    let mut rcon;
+    //Without -a or -S, the first positional argument is the profile if one has that name
+    if args.server.is_none() && args.address.is_none() && !commands.is_empty() {
+        match load_config(&args.config) {
+            Ok((config, _)) => {
+                if config.profile(&commands[0]).is_some() {
+                    args.server = Some(commands.remove(0));
+                }
+            }
+            Err(e) => {
+                print_if_not_silent(e.as_str().red(), silent);
+                return 1;
+            }
+        }
+    }
 
-    match rcon::RCONConnection::new(&args.address, args.port, pid as i32).await {
-        Ok(r) => {
-            print_if_not_silent("Connected to RCON.".white(), &args);
-            rcon = r
+    if commands.is_empty() {
+        return run_shell(&args, None, None).await;
+    }
+    let steps = script::from_commands(&commands, legacy.wait);
+    let options = ExecOptions {
+        fail_fast: false,
+        fail_on_error: !legacy.no_fail_on_error,
+        silent,
+        output: legacy.output,
+    };
+    return run_exec(&args, &steps, options).await;
+}
+
+///`log_session` and `log_format` override the profile's, they're only options of the shell subcommand
+async fn run_shell(
+    args: &ConnectArgs,
+    log_session: Option<PathBuf>,
+    log_format: Option<LogFormat>,
+) -> i32 {
+    let (profile, mut rcon) = match connect(args, false).await {
+        Ok(c) => c,
+        Err((msg, code)) => {
+            println!("{}", msg.as_str().red());
+            return code;
         }
-        Err(e) => {
-            print_if_not_silent("Failed to connect to server. Is it online?".red(), &args);
-            std::process::exit(1);
+    };
+
+    //A session that was asked to be logged isn't started without the log
+    let log = match log_session.or(profile.log_session.clone()) {
+        Some(path) => {
+            let format = log_format.or(profile.log_format).unwrap_or_default();
+            match SessionLog::open(&path, format) {
+                Ok(log) => Some(log),
+                Err(e) => {
+                    let msg = format!("Could not open session log {}. {}", path.display(), e);
+                    println!("{}", msg.red());
+                    return 1;
+                }
+            }
         }
+        None => None,
+    };
+
+    //history is kept per profile, or per address for servers without one
+    let key = match &args.server {
+        Some(name) => name.clone(),
+        None => format!(
+            "{}:{}",
+            profile.address,
+            profile.port.unwrap_or(DEFAULT_PORT)
+        ),
+    };
+    let mut history = HistoryOptions::new(&key);
+    if let (Some(history), Ok((config, _))) = (&mut history, load_config(&args.config)) {
+        history.size = config.history_size.unwrap_or(history.size);
+        history.dedup = config.history_dedup.unwrap_or(history.dedup);
+    }
+
+    println!("Creating a {} prompt.", rcon.game());
+    let options = ShellOptions {
+        theme: profile.theme.unwrap_or_default(),
+        history,
+        log,
+    };
+    let mut shell = RCONShell::new(&mut rcon, profile.address.clone(), options);
+
+    if let Err(e) = shell.run().await {
+        println!("Shell exited with error: {}", e);
+        return exit_code(&e);
     }
+    return 0;
+}
 
-    match rcon.auth(&args.password).await {
-        Ok(_) => {
-            print_if_not_silent("Logged in.".white(), &args);
+struct ExecOptions {
+    ///Stop at the first failure instead of running the rest and reporting the first failure at the end
+    fail_fast: bool,
+    ///Count responses the game reports as errors as failures
+    fail_on_error: bool,
+    silent: bool,
+    output: OutputFormat,
+}
+
+async fn run_exec(args: &ConnectArgs, steps: &[script::Line], options: ExecOptions) -> i32 {
+    let silent = options.silent;
+    let json = options.output == OutputFormat::Json;
+    //with JSON output, stdout is kept for the JSON and anything else goes to stderr
+    let (_, mut rcon) = match connect(args, silent || json).await {
+        Ok(c) => c,
+        Err((msg, code)) => {
+            if json {
+                eprintln!("{}", msg);
+            } else {
+                print_if_not_silent(msg.as_str().red(), silent);
+            }
+            return code;
         }
-        Err(_) => {
-            print_if_not_silent("Failed to log in.".red(), &args);
-            std::process::exit(1);
+    };
+
+    //exit code of the first thing that went wrong. Unless failing fast, the rest of the steps still run
+    let mut failure = 0;
+    let mut last_response: Option<String> = None;
+
+    for line in steps {
+        let code = match &line.step {
+            Step::Wait(duration) => {
+                tokio::time::sleep(*duration).await;
+                0
+            }
+            Step::Expect(regex) => match &last_response {
+                Some(response) if regex.is_match(response) => 0,
+                _ => {
+                    let msg = format!(
+                        "Line {}: the response didn't match {}",
+                        line.number,
+                        regex.as_str()
+                    );
+                    eprintln!("{}", msg.as_str().red());
+                    EXPECT_FAILED
+                }
+            },
+            Step::Command(cmd) => {
+                last_response = None;
+                let start = Instant::now();
+
+                let result = match rcon.split_command(cmd) {
+                    Ok(parts) => {
+                        if parts.len() > 1 {
+                            let msg = format!(
+                                "Command is over the server's limit of {} bytes, sending it as {} commands.",
+                                rcon.command_limit(),
+                                parts.len()
+                            );
+                            if json {
+                                eprintln!("{}", msg);
+                            } else {
+                                print_if_not_silent(msg.as_str().yellow(), silent);
+                            }
+                        }
+                        rcon.send_command(cmd).await
+                    }
+                    Err(e) => Err(e),
+                };
+
+                let latency = start.elapsed();
+                let classify = GameMapper::get_classify_fn(rcon.game());
+                let classification = result.as_deref().ok().map(classify);
+
+                if json {
+                    let record = CommandRecord {
+                        command: cmd,
+                        body: result.as_deref().ok(),
+                        classification: classification.as_ref(),
+                        //microseconds are plenty, and keep the output readable
+                        latency_ms: (latency.as_secs_f64() * 1_000_000.0).round() / 1000.0,
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                    };
+                    println!("{}", serde_json::to_string(&record).unwrap());
+                }
+
+                match result {
+                    Ok(s) => {
+                        if !json {
+                            print_if_not_silent(s.as_str().white(), silent);
+                        }
+                        last_response = Some(s);
+
+                        match classification.and_then(|c| c.error) {
+                            Some(e) if options.fail_on_error => response_exit_code(e),
+                            _ => 0,
+                        }
+                    }
+                    Err(e) => {
+                        if !json {
+                            match e {
+                                RconError::CommandTooLong { .. } => eprintln!("{}. Not sent.", e),
+                                _ => eprintln!("{}", e),
+                            }
+                        }
+                        let code = exit_code(&e);
+                        if e.is_fatal() {
+                            return code;
+                        }
+                        code
+                    }
+                }
+            }
+        };
+
+        if code != 0 {
+            if options.fail_fast {
+                return code;
+            }
+            if failure == 0 {
+                failure = code;
+            }
         }
     }
 
-    if args.commands.len() > 0 {
-        for cmd in &args.commands {
-            match rcon.send_command(cmd.trim()).await {
-                Ok(s) => {
-                    if args.silent == None {
-                        print_if_not_silent(s.as_str().white(), &args);
-                    }
+    return failure;
+}
+
+///One line of `--output json`
+#[derive(Serialize)]
+struct CommandRecord<'a> {
+    command: &'a str,
+    body: Option<&'a str>,
+    classification: Option<&'a Classification>,
+    latency_ms: f64,
+    error: Option<String>,
+}
+
+///Runs the command every interval until it's interrupted or the response matches --until. Errors are shown in place
+/// of the response, and a lost connection is retried on the next run, so a server restart doesn't end the watch.
+async fn run_watch(args: &ConnectArgs, command: &str, options: &WatchCommand) -> i32 {
+    //rejects negative, infinite and NaN intervals as well as ones too long to sleep for
+    let interval = match Duration::try_from_secs_f64(options.interval) {
+        Ok(interval) if !interval.is_zero() => interval.max(MIN_WATCH_INTERVAL),
+        _ => {
+            let msg = format!(
+                "The interval has to be a positive number of seconds, not {:?}.",
+                options.interval
+            );
+            eprintln!("{}", msg.red());
+            return 1;
+        }
+    };
+
+    let (profile, mut rcon) = match connect(args, true).await {
+        Ok(c) => c,
+        Err((msg, code)) => {
+            println!("{}", msg.as_str().red());
+            return code;
+        }
+    };
+
+    let server = args.server.clone().unwrap_or(profile.address);
+    let mut stdout = std::io::stdout();
+    let mut previous = Vec::<String>::new();
+    let mut matched = false;
+    let mut connected = true;
+
+    loop {
+        if !connected {
+            connected = rcon.reconnect().await.is_ok();
+        }
+
+        let (response, formatted) = match rcon.send_command(command).await {
+            Ok(r) => {
+                let formatted = rcon.format_response(&r);
+                (r, formatted)
+            }
+            Err(e) => {
+                connected = !e.is_fatal();
+                let msg = e.to_string();
+                let styled = vec![(msg.clone(), ContentStyle::new().red())];
+                (msg, styled)
+            }
+        };
+
+        let header = format!(
+            "Every {}s: {}    {}    {}",
+            interval.as_secs_f64(),
+            command,
+            server,
+            chrono::Local::now().format("%H:%M:%S")
+        );
+        previous = match watch::draw(&mut stdout, &header, formatted, &previous) {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        };
+
+        if let Some(regex) = &options.beep {
+            let matches = regex.is_match(&response);
+            if matches && !matched {
+                print!("\x07");
+                let _ = stdout.flush();
+            }
+            matched = matches;
+        }
+        if let Some(regex) = &options.until {
+            if regex.is_match(&response) {
+                return 0;
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return 0,
+        }
+    }
+}
+
+///Works out what exec should run: a script from -f, a script from stdin when the only command is `-`, or the
+/// commands themselves
+fn exec_steps(
+    file: &Option<PathBuf>,
+    commands: &[String],
+    wait: Option<u64>,
+) -> Result<Vec<script::Line>, String> {
+    let source = match (file, commands) {
+        (Some(_), [_, ..]) => {
+            return Err(String::from(
+                "Give either commands or a script file, not both.",
+            ))
+        }
+        (Some(path), []) if path.as_os_str() != "-" => std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}. {}", path.display(), e))?,
+        (Some(_), []) => read_stdin()?,
+        //Only on its own is it the stdin marker, among other commands it's sent like any of them
+        (None, [only]) if only == "-" => read_stdin()?,
+        (None, []) => return Err(String::from("No commands given.")),
+        (None, commands) => return Ok(script::from_commands(commands, wait)),
+    };
+
+    //Scripts say where to wait themselves, and a wait after every line would silently double up with their @wait
+    if wait.is_some() {
+        return Err(String::from(
+            "-w only applies to commands given on the command line. Use @wait in scripts.",
+        ));
+    }
+
+    return script::parse(&source);
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut source = String::new();
+    std::io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|e| format!("Failed to read stdin. {}", e))?;
+    return Ok(source);
+}
+
+async fn run_servers(cmd: ServersCommand) -> i32 {
+    let (mut config, path) = match load_config(&cmd.config) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e.as_str().red());
+            return 1;
+        }
+    };
+
+    match cmd.command {
+        ServersSubcommand::Add(add) => {
+            if config.profile(&add.name).is_some() && !add.force {
+                let msg = format!(
+                    "A profile named {} already exists. Use --force to replace it.",
+                    add.name
+                );
+                eprintln!("{}", msg.as_str().red());
+                return 1;
+            }
+            if add.password.is_some() {
+                let msg = format!(
+                    "The password is stored in plain text in {}.",
+                    path.display()
+                );
+                println!("{}", msg.as_str().yellow());
+            }
+
+            let profile = Profile {
+                address: add.address,
+                port: add.port,
+                game: add.game,
+                password: add.password,
+                password_file: add.password_file,
+                password_env: add.password_env,
+                connect_timeout: add.connect_timeout,
+                timeout: add.timeout,
+                theme: add.theme,
+                log_session: add.log_session,
+                log_format: add.log_format,
+                tls: add.tls,
+                tls_ca: add.tls_ca,
+                tls_fingerprint: add.tls_fingerprint,
+                tls_sni: add.tls_sni,
+            };
+            config.profiles.insert(add.name.clone(), profile);
+            if let Err(e) = config.save(&path) {
+                eprintln!("{}", e.to_string().as_str().red());
+                return 1;
+            }
+            println!("Added {} to {}.", add.name, path.display());
+        }
+        ServersSubcommand::List(list) => {
+            if list.names {
+                for name in config.profiles.keys() {
+                    println!("{}", name);
                 }
-                Err(e) => eprintln!("{}", e),
+                return 0;
             }
 
-            if args.wait.is_some() {
-                sleep(Duration::from_secs(args.wait.unwrap()))
+            if config.profiles.is_empty() {
+                println!("No profiles in {}.", path.display());
+            }
+            let width = config.profiles.keys().map(|n| n.len()).max().unwrap_or(0);
+            for (name, profile) in &config.profiles {
+                println!(
+                    "{}  {}:{}  {}",
+                    format!("{:width$}", name).as_str().bold(),
+                    profile.address,
+                    profile.port.unwrap_or(DEFAULT_PORT),
+                    profile.game.clone().unwrap_or(Game::GENERIC),
+                );
+            }
+        }
+        ServersSubcommand::Remove(remove) => {
+            if config.profiles.remove(&remove.name).is_none() {
+                let msg = format!("No profile named {} in {}", remove.name, path.display());
+                eprintln!("{}", msg.as_str().red());
+                return 1;
+            }
+            if let Err(e) = config.save(&path) {
+                eprintln!("{}", e.to_string().as_str().red());
+                return 1;
             }
+            println!("Removed {}.", remove.name);
         }
+        ServersSubcommand::Test(test) => {
+            let args = ConnectArgs {
+                server: Some(test.name.clone()),
+                config: Some(path),
+                ..Default::default()
+            };
 
-        std::process::exit(0);
+            let start = Instant::now();
+            if let Err((msg, code)) = connect(&args, false).await {
+                println!("{}", msg.as_str().red());
+                return code;
+            }
+            println!(
+                "{} is working, took {} ms.",
+                test.name,
+                start.elapsed().as_millis()
+            );
+        }
     }
 
-    println!("Creating a {} prompt.", args.game);
-    let mut shell = RCONShell::new(&mut rcon, args.game, args.address);
+    return 0;
+}
+
+///Connects and logs in with the given options. On failure, returns what went wrong and the exit code for it.
+async fn connect(args: &ConnectArgs, silent: bool) -> Result<(Profile, RconClient), (String, i32)> {
+    let profile = resolve_profile(args).map_err(|e| (e, 1))?;
+    let password = read_password(args, &profile).map_err(|e| (e, 1))?;
+
+    let mut builder = RconClient::builder()
+        .address(profile.address.as_str())
+        .port(profile.port.unwrap_or(DEFAULT_PORT))
+        .password(password.as_str())
+        .connect_timeout(seconds(profile.connect_timeout.unwrap_or(10)))
+        .timeout(seconds(profile.timeout.unwrap_or(30)))
+        .max_response_size(args.max_response_size)
+        .encoding(args.encoding)
+        .command_limit(args.command_limit)
+        .game(profile.game.clone().unwrap_or(Game::GENERIC));
 
-    match shell.run().await {
+    //Any of the TLS options implies TLS
+    if profile.tls
+        || profile.tls_ca.is_some()
+        || profile.tls_fingerprint.is_some()
+        || profile.tls_sni.is_some()
+    {
+        builder = builder.tls(TlsOptions {
+            ca_file: profile.tls_ca.clone(),
+            fingerprint: profile.tls_fingerprint.clone(),
+            server_name: profile.tls_sni.clone(),
+        });
+    }
+
+    return match builder.connect().await {
+        Ok(r) => {
+            print_if_not_silent("Connected to RCON.".white(), silent);
+            print_if_not_silent("Logged in.".white(), silent);
+            Ok((profile, r))
+        }
         Err(e) => {
-            println!("Shell exited with error: {}", e);
-            std::process::exit(1)
+            let msg = match e {
+                RconError::AuthRejected => format!("Failed to log in. {}", e),
+                _ => format!("Failed to connect to server. {}", e),
+            };
+            Err((msg, exit_code(&e)))
+        }
+    };
+}
+
+///Reads the config file from the given path, or the default one
+fn load_config(path: &Option<PathBuf>) -> Result<(Config, PathBuf), String> {
+    let path = match path {
+        Some(p) => p.clone(),
+        None => Config::default_path().map_err(|e| e.to_string())?,
+    };
+    let config = Config::load(&path).map_err(|e| e.to_string())?;
+
+    return Ok((config, path));
+}
+
+///Looks up the server profile, if any, and applies the options given on the command line on top of it
+fn resolve_profile(args: &ConnectArgs) -> Result<Profile, String> {
+    let mut profile = match &args.server {
+        Some(name) => {
+            let (config, path) = load_config(&args.config)?;
+            config
+                .profile(name)
+                .cloned()
+                .ok_or_else(|| format!("No profile named {} in {}", name, path.display()))?
         }
-        Ok(_) => {}
+        None => Profile::default(),
+    };
+
+    if let Some(address) = &args.address {
+        profile.address = address.clone();
     }
+    if profile.address.is_empty() {
+        return Err(String::from(
+            "No address given. Use -a, or name a profile from the config file.",
+        ));
+    }
+
+    profile.port = args.port.or(profile.port);
+    profile.game = args.game.clone().or(profile.game);
+    profile.connect_timeout = args.connect_timeout.or(profile.connect_timeout);
+    profile.timeout = args.timeout.or(profile.timeout);
+    profile.theme = args.theme.or(profile.theme);
+    profile.tls |= args.tls;
+    profile.tls_ca = args.tls_ca.clone().or(profile.tls_ca);
+    profile.tls_fingerprint = args.tls_fingerprint.clone().or(profile.tls_fingerprint);
+    profile.tls_sni = args.tls_sni.clone().or(profile.tls_sni);
+
+    return Ok(profile);
+}
+
+///Gets the password from the first source that has one: -P, --password-file, the profile, FRCON_PASSWORD, and finally
+/// a prompt
+fn read_password(args: &ConnectArgs, profile: &Profile) -> Result<Zeroizing<String>, String> {
+    if let Some(password) = &args.password {
+        return Ok(Zeroizing::new(password.clone()));
+    }
+
+    if let Some(path) = args
+        .password_file
+        .as_ref()
+        .or(profile.password_file.as_ref())
+    {
+        let contents = Zeroizing::new(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}. {}", path.display(), e))?,
+        );
+        //editors like to add a trailing newline, which is never part of the password
+        return Ok(Zeroizing::new(
+            contents.trim_end_matches(['\n', '\r']).to_string(),
+        ));
+    }
+
+    if let Some(password) = &profile.password {
+        return Ok(Zeroizing::new(password.clone()));
+    }
+
+    if let Some(var) = &profile.password_env {
+        return std::env::var(var).map(Zeroizing::new).map_err(|_| {
+            format!(
+                "The profile reads the password from {}, which isn't set.",
+                var
+            )
+        });
+    }
+
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(Zeroizing::new(password));
+    }
+
+    return rpassword::prompt_password("Password: ")
+        .map(Zeroizing::new)
+        .map_err(|_| {
+            format!(
+                "No password given. Use -P, --password-file or {}.",
+                PASSWORD_ENV
+            )
+        });
+}
+
+///Turns a timeout option into a Duration, with 0 meaning no timeout
+fn seconds(secs: u64) -> Option<Duration> {
+    return match secs {
+        0 => None,
+        n => Some(Duration::from_secs(n)),
+    };
+}
+
+///Exit codes for each kind of failure, so scripts can react without parsing the message. Documented in the README.
+fn exit_code(e: &RconError) -> i32 {
+    return match e {
+        RconError::Io(_) => 1,
+        RconError::ConnectionRefused(_) => 2,
+        RconError::Dns(_) => 3,
+        RconError::Timeout => 4,
+        RconError::AuthRejected => 5,
+        RconError::CommandTooLong { .. } => 6,
+        RconError::MalformedPacket(_) | RconError::Protocol(_) | RconError::InvalidUtf8 => 7,
+        RconError::Disconnected => 8,
+        RconError::Tls(_) => 9,
+    };
+}
+
+///Exit codes for responses the game reports as errors, after the ones for connection failures
+fn response_exit_code(e: ResponseError) -> i32 {
+    return match e {
+        ResponseError::InvalidCommand => 11,
+        ResponseError::NotFound => 12,
+    };
 }
 
 fn print_version() {
@@ -134,8 +799,8 @@ fn print_version() {
     println!("Licensed under MIT");
 }
 
-fn print_if_not_silent(output: StyledContent<&str>, args: &Args) {
-    if args.silent == None {
+fn print_if_not_silent(output: StyledContent<&str>, silent: bool) {
+    if !silent {
         println!("{}", output);
     }
 }
@@ -0,0 +1,71 @@
+///A command for the shell itself rather than the server. They're typed with a leading colon, e.g. `:help`.
+pub struct MetaCommand {
+    pub name: &'static str,
+    ///Arguments as shown by `:help`
+    pub args: &'static str,
+    pub description: &'static str,
+    ///Possible values of the argument, for completion
+    pub values: &'static [&'static str],
+}
+
+pub const META_COMMANDS: &[MetaCommand] = &[
+    MetaCommand {
+        name: "help",
+        args: "",
+        description: "List the shell's own commands",
+        values: &[],
+    },
+    MetaCommand {
+        name: "history",
+        args: "[text]",
+        description: "List this server's history, or search the history of every server",
+        values: &[],
+    },
+    MetaCommand {
+        name: "reconnect",
+        args: "",
+        description: "Open a new connection and log in again",
+        values: &[],
+    },
+    MetaCommand {
+        name: "game",
+        args: "[name]",
+        description: "Show the game profile, or switch to another one",
+        values: &["minecraft", "generic"],
+    },
+    MetaCommand {
+        name: "clear",
+        args: "",
+        description: "Clear the screen",
+        values: &[],
+    },
+    MetaCommand {
+        name: "timing",
+        args: "[on|off]",
+        description: "Show how long the server took to respond",
+        values: &["on", "off"],
+    },
+    MetaCommand {
+        name: "source",
+        args: "<file>",
+        description: "Run the commands in a file, one per line",
+        values: &[],
+    },
+    MetaCommand {
+        name: "save-transcript",
+        args: "<file>",
+        description: "Write the commands sent so far and their responses to a file",
+        values: &[],
+    },
+    MetaCommand {
+        name: "quit",
+        args: "",
+        description: "Leave the shell",
+        values: &[],
+    },
+];
+
+///Looks up a meta-command by name, without the colon
+pub fn find(name: &str) -> Option<&'static MetaCommand> {
+    return META_COMMANDS.iter().find(|c| c.name == name);
+}
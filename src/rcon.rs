@@ -1,13 +1,24 @@
-use std::io;
+use std::{str::FromStr, time::Duration};
 
+use futures_util::{SinkExt, StreamExt};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{lookup_host, TcpStream},
+    time::timeout,
 };
+use tokio_rustls::{rustls::pki_types::ServerName, TlsConnector};
+use tokio_util::{
+    bytes::{Buf, BufMut, BytesMut},
+    codec::{Decoder, Encoder, Framed},
+};
+use zeroize::Zeroizing;
 
-#[derive(Debug)]
+use crate::error::RconError;
+use crate::transport::{TlsOptions, Transport};
+
+#[derive(Debug, Clone, PartialEq)]
 ///All possible RCON command types
-enum RCONCommand {
+pub enum RCONCommand {
     ServerAuth,
     ServerAuthResponse,
     ServerExec,
@@ -45,21 +56,29 @@ const PACKET_SIZE_CONST: usize = 10;
 //max size of the body in bytes
 const MAX_PACKET_SIZE: usize = 4096;
 
+///The longest command the protocol allows, in bytes. Games may accept less, see [`crate::games::GameMapper::get_command_limit`].
+pub const MAX_COMMAND_SIZE: usize = MAX_PACKET_SIZE - PACKET_SIZE_CONST;
+
+//Default limit on the declared length of incoming packets. The spec caps responses at 4096 bytes, but Minecraft splits
+//at 4096 characters rather than bytes and some servers don't split at all, so there's plenty of headroom.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+///A single RCON packet. Read and written through [`RconCodec`].
 #[derive(Debug)]
-struct RCONPacket {
+pub struct RCONPacket {
     length: i32,
     id: i32,
     command: RCONCommand,
-    body: String,
+    body: Vec<u8>,
 }
 
 impl RCONPacket {
-    pub fn new(id: i32, command: RCONCommand, body: String) -> io::Result<RCONPacket> {
-        if body.len() > (MAX_PACKET_SIZE - PACKET_SIZE_CONST) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Command exceeds max size of body",
-            ));
+    pub fn new(id: i32, command: RCONCommand, body: String) -> Result<RCONPacket, RconError> {
+        if body.len() > MAX_COMMAND_SIZE {
+            return Err(RconError::CommandTooLong {
+                length: body.len(),
+                max: MAX_COMMAND_SIZE,
+            });
         }
 
         //length = id: 4, command: 4, null terminator after command: 1, null terminator at end: 1
@@ -67,105 +86,571 @@ impl RCONPacket {
             length: (PACKET_SIZE_CONST + body.len()) as i32,
             id,
             command,
-            body,
+            body: body.into_bytes(),
         })
     }
 
-    pub async fn serialize(&self, stream: &mut TcpStream) -> io::Result<()> {
-        let mut buf = Vec::with_capacity(self.length as usize);
+    pub fn id(&self) -> i32 {
+        return self.id;
+    }
 
-        //RCON requires LE encoding
-        buf.extend_from_slice(&self.length.to_le_bytes());
-        buf.extend_from_slice(&self.id.to_le_bytes());
-        buf.extend_from_slice(&self.command.to_i32().to_le_bytes());
-        buf.extend_from_slice(self.body.as_bytes());
+    pub fn command(&self) -> &RCONCommand {
+        return &self.command;
+    }
 
-        //two null terminator bytes (one for the body and one required by the RCON spec)
-        buf.extend_from_slice(&[0x00, 0x00]);
+    ///The raw body. Responses are only decoded once all their packets are in, so a character split between two packets
+    /// isn't mangled.
+    pub fn body(&self) -> &[u8] {
+        return &self.body;
+    }
+}
 
-        stream.write_all(&buf).await?;
+///How to turn response bodies into text. The spec says bodies are ASCII, but servers and plugins send all sorts,
+/// e.g. Latin-1 or a § color code cut in half.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Encoding {
+    ///Fail with [`RconError::InvalidUtf8`] on anything that isn't UTF-8. Only the command is failed, the connection
+    /// stays usable.
+    Strict,
+    ///Replace invalid bytes with U+FFFD
+    #[default]
+    Lossy,
+    ///Read every byte as a Latin-1 character. Never fails, but mangles UTF-8.
+    Latin1,
+}
 
-        Ok(())
+impl Encoding {
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Result<String, RconError> {
+        return match self {
+            Encoding::Strict => {
+                String::from_utf8(bytes.to_vec()).map_err(|_| RconError::InvalidUtf8)
+            }
+            Encoding::Lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            Encoding::Latin1 => Ok(bytes.iter().map(|b| *b as char).collect()),
+        };
+    }
+}
+
+///Required for argh
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Strict => write!(f, "strict"),
+            Encoding::Lossy => write!(f, "lossy"),
+            Encoding::Latin1 => write!(f, "latin1"),
+        }
     }
+}
+
+///Required for argh
+impl FromStr for Encoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Encoding::Strict),
+            "lossy" => Ok(Encoding::Lossy),
+            "latin1" => Ok(Encoding::Latin1),
+            _ => Err(format!(
+                "Invalid encoding {}, expected strict, lossy or latin1",
+                s
+            )),
+        }
+    }
+}
 
-    pub async fn deserialize(stream: &mut TcpStream) -> io::Result<RCONPacket> {
-        //buffer to read exactly one i32 at a time
-        let mut buf = [0u8; 4];
+///Encodes and decodes RCON packets on any byte stream. Use it with [`Framed`], or call it directly on a buffer for
+/// blocking IO and test harnesses. Decoded packets are treated as server responses, and their bodies are left as raw
+/// bytes.
+///
+///Any error from [`Decoder::decode`] ends a [`Framed`] stream, so only problems that really leave the stream unusable
+/// are reported here. Bodies which aren't valid text are the connection's concern, see [`Encoding`].
+#[derive(Debug, Clone)]
+pub struct RconCodec {
+    max_response_size: usize,
+}
 
-        stream.read_exact(&mut buf).await?;
-        let length = i32::from_le_bytes(buf);
-        stream.read_exact(&mut buf).await?;
-        let id = i32::from_le_bytes(buf);
-        stream.read_exact(&mut buf).await?;
-        let command = i32::from_le_bytes(buf);
-        let body_length = length - (PACKET_SIZE_CONST as i32);
-        let mut body_buffer = Vec::with_capacity(body_length as usize);
+impl Default for RconCodec {
+    fn default() -> Self {
+        return RconCodec::new(DEFAULT_MAX_RESPONSE_SIZE);
+    }
+}
 
-        stream
-            .take(body_length as u64)
-            .read_to_end(&mut body_buffer)
-            .await?;
+impl RconCodec {
+    ///`max_response_size` is the largest length field accepted from the other side. Anything above it is rejected
+    /// before a single byte of the body is buffered.
+    pub fn new(max_response_size: usize) -> RconCodec {
+        return RconCodec { max_response_size };
+    }
 
-        let body = String::from_utf8(body_buffer)
-            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+    pub fn max_response_size(&self) -> usize {
+        return self.max_response_size;
+    }
+}
+
+impl Decoder for RconCodec {
+    type Item = RCONPacket;
+    type Error = RconError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<RCONPacket>, RconError> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
+
+        //The length field comes straight from the wire and doesn't count itself. It has to be checked before it's
+        //used for anything, a negative or huge value would otherwise underflow or reserve gigabytes.
+        let length = i32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        if length < PACKET_SIZE_CONST as i32 {
+            return Err(RconError::MalformedPacket(format!(
+                "declared length {} is shorter than the packet header",
+                length
+            )));
+        }
+        if length as usize > self.max_response_size {
+            return Err(RconError::MalformedPacket(format!(
+                "declared length {} exceeds the maximum of {} bytes",
+                length, self.max_response_size
+            )));
+        }
+
+        let body_length = length as usize - PACKET_SIZE_CONST;
+        let packet_length = 4 + length as usize;
+        if src.len() < packet_length {
+            src.reserve(packet_length - src.len());
+            return Ok(None);
+        }
 
-        //buffer for terminating null pointers at the end of response
-        let mut buf = [0u8; 2];
-        stream.read_exact(&mut buf).await?;
+        let mut packet = src.split_to(packet_length);
+        packet.advance(4);
+        let id = packet.get_i32_le();
+        let command = packet.get_i32_le();
+        let body_buffer = packet.split_to(body_length);
 
-        let packet = RCONPacket {
+        //what's left are the two null terminators
+        if packet[..] != [0x00, 0x00] {
+            return Err(RconError::MalformedPacket(String::from(
+                "packet isn't terminated by two null bytes",
+            )));
+        }
+
+        Ok(Some(RCONPacket {
             length,
             id,
             command: RCONCommand::from_i32(command, true),
-            body,
-        };
+            body: body_buffer.to_vec(),
+        }))
+    }
+}
 
-        Ok(packet)
+impl Encoder<RCONPacket> for RconCodec {
+    type Error = RconError;
+
+    fn encode(&mut self, packet: RCONPacket, dst: &mut BytesMut) -> Result<(), RconError> {
+        dst.reserve(4 + packet.length as usize);
+
+        //RCON requires LE encoding
+        dst.put_i32_le(packet.length);
+        dst.put_i32_le(packet.id);
+        dst.put_i32_le(packet.command.to_i32());
+        dst.put_slice(&packet.body);
+
+        //two null terminator bytes (one for the body and one required by the RCON spec)
+        dst.put_slice(&[0x00, 0x00]);
+
+        Ok(())
     }
 }
 
-pub struct RCONConnection {
-    id: i32,
-    stream: TcpStream,
+///Where a connection was opened to, so it can be opened again
+struct Target {
+    address: String,
+    port: u16,
+    connect_timeout: Option<Duration>,
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+///A raw connection to an RCON server over any async byte stream. [`crate::RconClient`] wraps this with a friendlier interface.
+pub struct RCONConnection<S = Transport> {
+    next_id: i32,
+    framed: Framed<S, RconCodec>,
+    encoding: Encoding,
+    target: Option<Target>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
 }
 
-impl RCONConnection {
+impl RCONConnection<Transport> {
+    ///Connects to the server. `connect_timeout` covers both resolving the address and opening the socket, None waits forever.
     pub async fn new(
         address: &str,
         port: u16,
-        id: i32,
-    ) -> Result<RCONConnection, tokio::io::Error> {
-        let conn = RCONConnection {
-            stream: TcpStream::connect(String::from(address) + ":" + port.to_string().as_str())
-                .await?,
-            id,
+        connect_timeout: Option<Duration>,
+    ) -> Result<RCONConnection, RconError> {
+        let target = Target {
+            address: String::from(address),
+            port,
+            connect_timeout,
+            tls: None,
         };
 
+        return Self::open_target(target).await;
+    }
+
+    ///Connects to the server over TLS. The connect timeout includes the handshake.
+    pub async fn new_tls(
+        address: &str,
+        port: u16,
+        connect_timeout: Option<Duration>,
+        tls: &TlsOptions,
+    ) -> Result<RCONConnection, RconError> {
+        let target = Target {
+            address: String::from(address),
+            port,
+            connect_timeout,
+            tls: Some(tls.connector(address)?),
+        };
+
+        return Self::open_target(target).await;
+    }
+
+    async fn open_target(target: Target) -> Result<RCONConnection, RconError> {
+        let mut conn = Self::from_stream(Self::open(&target).await?);
+        conn.target = Some(target);
+
         return Ok(conn);
     }
 
-    pub async fn auth(&mut self, password: &str) -> std::io::Result<()> {
-        //this should almost never panic, cleaner to just unwrap
-        let packet = RCONPacket::new(self.id, RCONCommand::ServerAuth, String::from(password))?;
-        packet.serialize(&mut self.stream).await.unwrap();
+    ///Replaces the socket with a new one to the same server, keeping all settings. The new connection still has to be authenticated.
+    pub async fn reconnect(&mut self) -> Result<(), RconError> {
+        let stream = match &self.target {
+            Some(t) => Self::open(t).await?,
+            None => {
+                return Err(RconError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Connection wasn't opened by frcon, so it can't be reopened",
+                )))
+            }
+        };
+        //keep the codec, and with it any limits set on the old connection
+        self.framed = Framed::new(stream, self.framed.codec().clone());
+        Ok(())
+    }
+
+    async fn open(target: &Target) -> Result<Transport, RconError> {
+        return match target.connect_timeout {
+            Some(t) => timeout(t, Self::handshake(target))
+                .await
+                .map_err(|_| RconError::Timeout)?,
+            None => Self::handshake(target).await,
+        };
+    }
 
-        let response = RCONPacket::deserialize(&mut self.stream).await?;
-        if response.id != self.id {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::ConnectionRefused,
-                "Failed to connect to Minecraft server",
-            ));
+    async fn handshake(target: &Target) -> Result<Transport, RconError> {
+        let stream = Self::connect(&target.address, target.port).await?;
+
+        return match &target.tls {
+            Some((connector, server_name)) => {
+                match connector.connect(server_name.clone(), stream).await {
+                    Ok(s) => Ok(Transport::Tls(Box::new(s))),
+                    //rustls reports certificate and protocol problems as InvalidData
+                    Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                        Err(RconError::Tls(e.to_string()))
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+            None => Ok(Transport::Plain(stream)),
+        };
+    }
+
+    async fn connect(address: &str, port: u16) -> Result<TcpStream, RconError> {
+        let host = String::from(address) + ":" + port.to_string().as_str();
+
+        //Resolving separately from connecting is the only way to tell a typo in the address apart from an offline server
+        let addrs: Vec<_> = lookup_host(&host)
+            .await
+            .map_err(|_| RconError::Dns(String::from(address)))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(RconError::Dns(String::from(address)));
         }
 
+        return match TcpStream::connect(addrs.as_slice()).await {
+            Ok(s) => Ok(s),
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                Err(RconError::ConnectionRefused(host))
+            }
+            Err(e) => Err(e.into()),
+        };
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> RCONConnection<S> {
+    ///Wraps an already open stream, e.g. a TLS stream, a Unix socket or one half of a tokio duplex pipe.
+    pub fn from_stream(stream: S) -> RCONConnection<S> {
+        return RCONConnection {
+            next_id: 1,
+            framed: Framed::new(stream, RconCodec::default()),
+            encoding: Encoding::default(),
+            target: None,
+            read_timeout: None,
+            write_timeout: None,
+        };
+    }
+
+    ///Sets how long to wait for each packet from the server. None waits forever.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    ///Sets how long sending a packet may take. None waits forever.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    ///Sets the largest packet accepted from the server, in bytes. Larger packets fail with [`RconError::MalformedPacket`].
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.framed.codec_mut().max_response_size = size;
+    }
+
+    ///Sets how response bodies are decoded. Defaults to [`Encoding::Lossy`].
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    async fn write_packet(&mut self, packet: RCONPacket) -> Result<(), RconError> {
+        return match self.write_timeout {
+            Some(t) => timeout(t, self.framed.send(packet))
+                .await
+                .map_err(|_| RconError::Timeout)?,
+            None => self.framed.send(packet).await,
+        };
+    }
+
+    async fn read_packet(&mut self) -> Result<RCONPacket, RconError> {
+        let packet = match self.read_timeout {
+            Some(t) => timeout(t, self.framed.next())
+                .await
+                .map_err(|_| RconError::Timeout)?,
+            None => self.framed.next().await,
+        };
+
+        //the stream ending is the only way to notice the server hung up between packets
+        return packet.unwrap_or(Err(RconError::Disconnected));
+    }
+
+    ///Logs in. Has to succeed before the server accepts any commands.
+    pub async fn auth(&mut self, password: &str) -> Result<(), RconError> {
+        let id = next_id(&mut self.next_id);
+        let packet = auth_bytes(id, password)?;
+
+        //Nothing else has been sent yet, so writing past the codec can't interleave with a buffered packet
+        let stream = self.framed.get_mut();
+        let write = async {
+            stream.write_all(&packet).await?;
+            stream.flush().await
+        };
+        match self.write_timeout {
+            Some(t) => timeout(t, write).await.map_err(|_| RconError::Timeout)??,
+            None => write.await?,
+        };
+
+        while auth_step(id, &self.read_packet().await?)? == Step::Continue {}
+
         Ok(())
     }
 
-    pub async fn send_command(&mut self, command: &str) -> std::io::Result<String> {
-        let packet = RCONPacket::new(self.id, RCONCommand::ServerExec, String::from(command))?;
-        packet.serialize(&mut self.stream).await.unwrap();
+    ///Sends a command and returns the full response body, reassembled if the server split it over several packets.
+    pub async fn send_command(&mut self, command: &str) -> Result<String, RconError> {
+        let id = next_id(&mut self.next_id);
+        let sentinel_id = next_id(&mut self.next_id);
+        for packet in command_packets(id, sentinel_id, command)? {
+            self.write_packet(packet).await?;
+        }
+
+        let mut body = Vec::new();
+        while command_step(id, sentinel_id, self.read_packet().await?, &mut body) == Step::Continue
+        {
+        }
+
+        //Decoded here rather than in the codec, so a body that isn't valid text only fails this command
+        return self.encoding.decode(&body);
+    }
+}
+
+//The protocol logic below is shared with the blocking connection, which only differs in how packets are moved
+
+#[derive(PartialEq)]
+pub(crate) enum Step {
+    Continue,
+    Done,
+}
+
+///Returns a fresh id for the next request. Ids stay positive, as -1 is what servers answer failed logins with
+pub(crate) fn next_id(next: &mut i32) -> i32 {
+    let id = *next;
+    *next = if id == i32::MAX { 1 } else { id + 1 };
+    return id;
+}
+
+///Serializes a login packet. Bypasses RCONPacket and the codec so the password only ever sits in a buffer which is
+/// wiped when dropped, rather than in a String or a write buffer that's freed without being cleared.
+pub(crate) fn auth_bytes(id: i32, password: &str) -> Result<Zeroizing<Vec<u8>>, RconError> {
+    if password.len() > MAX_COMMAND_SIZE {
+        return Err(RconError::CommandTooLong {
+            length: password.len(),
+            max: MAX_COMMAND_SIZE,
+        });
+    }
+
+    let length = (PACKET_SIZE_CONST + password.len()) as i32;
+    let mut buf = Zeroizing::new(Vec::with_capacity(4 + length as usize));
+    buf.extend_from_slice(&length.to_le_bytes());
+    buf.extend_from_slice(&id.to_le_bytes());
+    buf.extend_from_slice(&RCONCommand::ServerAuth.to_i32().to_le_bytes());
+    buf.extend_from_slice(password.as_bytes());
+    buf.extend_from_slice(&[0x00, 0x00]);
+
+    return Ok(buf);
+}
+
+///Checks a packet received in reply to a login with `id`
+pub(crate) fn auth_step(id: i32, response: &RCONPacket) -> Result<Step, RconError> {
+    //A failed login is always answered with id -1
+    if response.id == -1 {
+        return Err(RconError::AuthRejected);
+    }
+
+    return match response.command {
+        //Source servers send an empty SERVERDATA_RESPONSE_VALUE before the SERVERDATA_AUTH_RESPONSE
+        RCONCommand::ServerResponseValue if response.body.is_empty() => Ok(Step::Continue),
+        RCONCommand::ServerAuthResponse if response.id == id => Ok(Step::Done),
+        _ => Err(RconError::Protocol(format!(
+            "expected an auth response with id {}, got {:?} with id {}",
+            id, response.command, response.id
+        ))),
+    };
+}
+
+///Returns the packets to send for a command: the command itself, followed by a sentinel.
+///
+///Long responses are split over several packets and nothing marks the last one. Servers answer requests in order, so
+///the reply to an empty SERVERDATA_RESPONSE_VALUE sent straight after the command can only arrive after every fragment.
+///Minecraft doesn't mirror it, but still replies with "Unknown request 0" under the same id, which works just as well.
+pub(crate) fn command_packets(
+    id: i32,
+    sentinel_id: i32,
+    command: &str,
+) -> Result<[RCONPacket; 2], RconError> {
+    return Ok([
+        RCONPacket::new(id, RCONCommand::ServerExec, String::from(command))?,
+        RCONPacket::new(sentinel_id, RCONCommand::ServerResponseValue, String::new())?,
+    ]);
+}
+
+///Adds a packet received after sending a command to `body`, until the sentinel's reply shows up
+pub(crate) fn command_step(
+    id: i32,
+    sentinel_id: i32,
+    response: RCONPacket,
+    body: &mut Vec<u8>,
+) -> Step {
+    if response.id == sentinel_id {
+        return Step::Done;
+    }
+
+    //Anything else belongs to an earlier request, e.g. the extra packet Source sends after mirroring a sentinel,
+    //or a reply that arrived after we gave up on it. Attributing it to this command would be wrong, so it's dropped.
+    if response.id == id {
+        body.extend_from_slice(&response.body);
+    }
+
+    return Step::Continue;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(length: i32, id: i32, kind: i32, rest: &[u8]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        buf.put_i32_le(length);
+        buf.put_i32_le(id);
+        buf.put_i32_le(kind);
+        buf.put_slice(rest);
+        return buf;
+    }
+
+    fn decode(buf: &mut BytesMut) -> Result<Option<RCONPacket>, RconError> {
+        return RconCodec::new(100).decode(buf);
+    }
+
+    #[test]
+    fn decodes_a_packet_and_leaves_the_rest() {
+        let mut buf = raw(12, 7, 0, b"hi\0\0");
+        buf.put_slice(&[1, 2]);
+
+        let packet = decode(&mut buf).unwrap().unwrap();
+        assert_eq!(packet.id(), 7);
+        assert_eq!(packet.command(), &RCONCommand::ServerResponseValue);
+        assert_eq!(packet.body(), b"hi");
+        assert_eq!(&buf[..], &[1, 2]);
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_partial_packet() {
+        let mut buf = BytesMut::from(&[12u8, 0][..]);
+        assert!(decode(&mut buf).unwrap().is_none());
+
+        let mut buf = raw(12, 7, 0, b"h");
+        assert!(decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 13);
+    }
+
+    #[test]
+    fn rejects_a_negative_length() {
+        let mut buf = raw(-1, 7, 0, b"\0\0");
+        assert!(matches!(
+            decode(&mut buf),
+            Err(RconError::MalformedPacket(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_length_shorter_than_the_header() {
+        let mut buf = raw(9, 7, 0, b"\0");
+        assert!(matches!(
+            decode(&mut buf),
+            Err(RconError::MalformedPacket(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_length_over_the_maximum_before_buffering_it() {
+        let mut buf = raw(101, 7, 0, b"");
+        assert!(matches!(
+            decode(&mut buf),
+            Err(RconError::MalformedPacket(_))
+        ));
+        assert!(buf.capacity() < 101);
+    }
+
+    #[test]
+    fn rejects_missing_terminators() {
+        let mut buf = raw(12, 7, 0, b"hi\0x");
+        assert!(matches!(
+            decode(&mut buf),
+            Err(RconError::MalformedPacket(_))
+        ));
+    }
 
-        let response = RCONPacket::deserialize(&mut self.stream).await?;
+    #[test]
+    fn encodes_what_it_decodes() {
+        let packet = RCONPacket::new(3, RCONCommand::ServerResponseValue, "list".into()).unwrap();
+        let mut buf = BytesMut::new();
+        RconCodec::default().encode(packet, &mut buf).unwrap();
 
-        Ok(response.body)
+        let decoded = decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded.id(), 3);
+        assert_eq!(decoded.body(), b"list");
     }
 }
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use regex::Regex;
+
+///One thing for exec to do, from a script line or a command given on the command line
+pub enum Step {
+    Command(String),
+    ///`@wait 5s` pauses before the next line
+    Wait(Duration),
+    ///`@expect <regex>` checks the response of the command before it
+    Expect(Regex),
+}
+
+pub struct Line {
+    ///Line number in the script, for error messages
+    pub number: usize,
+    pub step: Step,
+}
+
+///Parses a script of one command per line. Blank lines and lines starting with `#` are skipped, and lines starting
+/// with `@` are directives. The whole script is parsed before anything is sent, so a typo on the last line doesn't
+/// leave a maintenance run half done.
+pub fn parse(source: &str) -> Result<Vec<Line>, String> {
+    let mut lines = Vec::new();
+
+    for (i, raw) in source.lines().enumerate() {
+        let number = i + 1;
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let step = match line.strip_prefix('@') {
+            None => Step::Command(line.to_string()),
+            Some(directive) => {
+                let (name, arg) = directive
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((directive, ""));
+                let arg = arg.trim();
+                match name {
+                    "wait" => Step::Wait(
+                        parse_duration(arg).map_err(|e| format!("Line {}: {}", number, e))?,
+                    ),
+                    "expect" => {
+                        if !lines
+                            .iter()
+                            .any(|l: &Line| matches!(l.step, Step::Command(_)))
+                        {
+                            return Err(format!(
+                                "Line {}: @expect has no command before it",
+                                number
+                            ));
+                        }
+                        Step::Expect(
+                            Regex::new(arg)
+                                .map_err(|e| format!("Line {}: invalid regex. {}", number, e))?,
+                        )
+                    }
+                    _ => return Err(format!("Line {}: unknown directive @{}", number, name)),
+                }
+            }
+        };
+
+        lines.push(Line { number, step });
+    }
+
+    return Ok(lines);
+}
+
+///Turns the commands given on the command line into steps, waiting between them if asked to
+pub fn from_commands(commands: &[String], wait: Option<u64>) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    for (i, cmd) in commands.iter().enumerate() {
+        lines.push(Line {
+            number: i + 1,
+            step: Step::Command(cmd.trim().to_string()),
+        });
+        if let Some(wait) = wait {
+            lines.push(Line {
+                number: i + 1,
+                step: Step::Wait(Duration::from_secs(wait)),
+            });
+        }
+    }
+
+    return lines;
+}
+
+///Parses durations like `5s`, `500ms`, `2m` or a bare number of seconds
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", s))?;
+    let seconds = match unit.trim() {
+        "" | "s" => number,
+        "ms" => number / 1000.0,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => {
+            return Err(format!(
+                "unknown unit in duration '{}'. Use ms, s, m or h",
+                s
+            ))
+        }
+    };
+
+    return Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("duration '{}' is too long", s));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        return match parse(source) {
+            Ok(_) => panic!("{:?} parsed", source),
+            Err(e) => e,
+        };
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let lines = parse("# maintenance\n\n  say hi  \n   # indented\nlist\n").unwrap();
+
+        let numbered: Vec<(usize, &str)> = lines
+            .iter()
+            .map(|l| match &l.step {
+                Step::Command(c) => (l.number, c.as_str()),
+                _ => panic!("line {} isn't a command", l.number),
+            })
+            .collect();
+        assert_eq!(numbered, [(3, "say hi"), (5, "list")]);
+    }
+
+    #[test]
+    fn parses_wait_units() {
+        let lines = parse("@wait 500ms\n@wait 5s\n@wait 2\n@wait 1.5m\n@wait 1h").unwrap();
+
+        let waits: Vec<Duration> = lines
+            .iter()
+            .map(|l| match l.step {
+                Step::Wait(d) => d,
+                _ => panic!("line {} isn't a wait", l.number),
+            })
+            .collect();
+        assert_eq!(
+            waits,
+            [
+                Duration::from_millis(500),
+                Duration::from_secs(5),
+                Duration::from_secs(2),
+                Duration::from_secs(90),
+                Duration::from_secs(3600),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_waits() {
+        assert_eq!(error("list\n@wait soon"), "Line 2: invalid duration 'soon'");
+        assert_eq!(
+            error("@wait 5d"),
+            "Line 1: unknown unit in duration '5d'. Use ms, s, m or h"
+        );
+        let huge = "9".repeat(400) + "h";
+        assert_eq!(
+            error(&format!("@wait {}", huge)),
+            format!("Line 1: duration '{}' is too long", huge)
+        );
+    }
+
+    #[test]
+    fn expect_follows_a_command() {
+        let lines = parse("list\n@expect There are \\d+").unwrap();
+        match &lines[1].step {
+            Step::Expect(regex) => assert!(regex.is_match("There are 3 of a max of 20")),
+            _ => panic!("line 2 isn't an expect"),
+        }
+
+        assert_eq!(
+            error("# header\n@expect done"),
+            "Line 2: @expect has no command before it"
+        );
+        assert!(error("list\n@expect (").starts_with("Line 2: invalid regex."));
+    }
+
+    #[test]
+    fn rejects_unknown_directives() {
+        assert_eq!(
+            error("list\n\n@sleep 5"),
+            "Line 3: unknown directive @sleep"
+        );
+    }
+}
@@ -3,57 +3,127 @@ use crossterm::style::{Attribute, ContentStyle, SetStyle, Stylize};
 use nu_ansi_term::Style;
 use std::borrow::Cow;
 use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use crate::client::RconClient;
+use crate::completer::RCONCompleter;
+use crate::error::RconError;
 use crate::games::{Game, GameMapper};
 use crate::highlighter::RCONHighlighter;
-use crate::rcon::RCONConnection;
+use crate::hinter::{FailedCommands, RCONHinter};
+use crate::history::HistoryOptions;
+use crate::meta::{self, META_COMMANDS};
+use crate::theme::Theme;
+use crate::transcript::{self, SessionLog};
 
 use reedline::{
-    default_emacs_keybindings, ColumnarMenu, DefaultCompleter, Emacs, KeyCode, KeyModifiers,
+    default_emacs_keybindings, ColumnarMenu, Emacs, FileBackedHistory, KeyCode, KeyModifiers,
     MenuBuilder, Prompt, PromptEditMode, PromptHistorySearch, Reedline, ReedlineEvent,
     ReedlineMenu, Signal,
 };
 
+//Delay before the first reconnection attempt. Doubled after every failure up to MAX_BACKOFF
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const RECONNECT_ATTEMPTS: u32 = 8;
+
+///The interactive shell. Reads commands with reedline, sends them through an [`RconClient`] and prints the formatted responses.
 pub struct RCONShell<'a> {
-    conn: &'a mut RCONConnection,
+    client: &'a mut RconClient,
     stdout: io::Stdout,
 
-    command_fn: &'a dyn Fn() -> Vec<String>,
-    response_fn: &'a dyn Fn(&str) -> Vec<(String, ContentStyle)>,
-
     line_editor: Reedline,
     prompt: RCONPrompt,
+    history: Option<HistoryOptions>,
+    failed: FailedCommands,
+    theme: Theme,
+
+    ///Print how long each response took, toggled with `:timing`
+    timing: bool,
+    ///Everything sent this session, for `:save-transcript`
+    transcript: Vec<transcript::Entry>,
+    ///Set while running a file with `:source`, which can't source another one
+    sourcing: bool,
+    log: Option<SessionLog>,
+}
+
+///Settings for the shell which don't concern the connection
+#[derive(Default)]
+pub struct ShellOptions {
+    pub theme: Theme,
+    ///Where to keep the command history. Without it, history only lasts for the session.
+    pub history: Option<HistoryOptions>,
+    ///Where to append a transcript of every command and response
+    pub log: Option<SessionLog>,
 }
 
 impl RCONShell<'_> {
-    pub fn new(conn: &mut RCONConnection, game: Game, ip: String) -> RCONShell {
-        let command_fn = GameMapper::get_command_fn(&game);
-        let response_fn = GameMapper::get_response_fn(&game);
+    pub fn new(client: &mut RconClient, ip: String, options: ShellOptions) -> RCONShell<'_> {
+        let history = options.history.as_ref().and_then(|h| match h.open() {
+            Ok(history) => Some(history),
+            Err(e) => {
+                let msg = format!(
+                    "Could not open {}, history won't be saved. {}",
+                    h.path().display(),
+                    e
+                );
+                println!("{}", msg.yellow());
+                None
+            }
+        });
+
+        let failed = options
+            .history
+            .as_ref()
+            .and_then(|h| h.failed().ok())
+            .unwrap_or_default();
+        let failed: FailedCommands = Arc::new(Mutex::new(failed));
+
+        let line_editor = Self::create_reedline(
+            client.commands(),
+            client.game().clone(),
+            options.theme,
+            history,
+            failed.clone(),
+        );
         RCONShell {
-            conn,
+            client,
             stdout: io::stdout(),
-            command_fn,
-            response_fn,
-            line_editor: Self::create_reedline((command_fn)(), game),
-            prompt: RCONPrompt::create(ip),
+            line_editor,
+            prompt: RCONPrompt::create(ip, options.theme),
+            history: options.history,
+            failed,
+            theme: options.theme,
+            timing: false,
+            transcript: Vec::new(),
+            sourcing: false,
+            log: options.log,
         }
     }
 
-    pub async fn run(&mut self) -> io::Result<()> {
-        println!("{}", "\nCTRL+C or CTRL+D to quit.");
+    pub async fn run(&mut self) -> Result<(), RconError> {
+        println!("\nCTRL+C, CTRL+D or :quit to quit. :help lists the shell's own commands.");
 
         self.shell_loop().await?;
         Ok(())
     }
 
-    async fn shell_loop(&mut self) -> std::io::Result<()> {
+    async fn shell_loop(&mut self) -> Result<(), RconError> {
         loop {
             let sig = self.line_editor.read_line(&self.prompt);
 
             match sig {
                 Ok(Signal::Success(buffer)) => {
-                    let res = self.conn.send_command(&buffer).await?;
-                    self.print_command_response(res)?;
+                    let keep_going = self.run_line(&buffer).await?;
+                    //Written after every command so that other shells and a crash don't lose it
+                    if let Err(e) = self.save_history() {
+                        println!("{}", format!("Could not save history. {}", e).yellow());
+                    }
+                    if !keep_going {
+                        println!("Exiting...");
+                        break;
+                    }
                 }
                 Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
                     println!("Exiting...");
@@ -66,9 +136,347 @@ impl RCONShell<'_> {
         Ok(())
     }
 
-    fn create_reedline(commands: Vec<String>, game: Game) -> Reedline {
-        let highlighter = RCONHighlighter::new(commands.clone(), game);
-        let completer = DefaultCompleter::new_with_wordlen(commands, 1);
+    ///Runs a meta-command or sends the line to the server. Returns whether the shell should keep running.
+    async fn run_line(&mut self, line: &str) -> Result<bool, RconError> {
+        return match line.trim_start().strip_prefix(':') {
+            //boxed because :source comes back here
+            Some(meta) => Box::pin(self.meta_command(meta)).await,
+            None => {
+                self.execute(line).await?;
+                Ok(true)
+            }
+        };
+    }
+
+    ///Sends a command and prints the response. If the connection drops, it is restored and the user is asked whether to send the command again.
+    async fn execute(&mut self, command: &str) -> Result<(), RconError> {
+        //Warn about oversized commands before anything is sent, servers tend to drop them silently
+        match self.client.split_command(command) {
+            Ok(parts) if parts.len() > 1 => println!(
+                "{}",
+                format!(
+                    "Command is over the server's limit of {} bytes, sending it as {} commands.",
+                    self.client.command_limit(),
+                    parts.len()
+                )
+                .yellow()
+            ),
+            Ok(_) => {}
+            Err(e) => {
+                println!("{}", format!("{}. Not sent.", e).red());
+                return Ok(());
+            }
+        }
+
+        if self.prompt.state != ConnectionState::Connected && !self.reconnect().await {
+            return Ok(());
+        }
+
+        loop {
+            let time = chrono::Utc::now();
+            let sent = Instant::now();
+            let result = self.client.send_command(command).await;
+            let elapsed = sent.elapsed();
+            let entry = transcript::Entry {
+                time,
+                command: command.to_string(),
+                response: result
+                    .as_ref()
+                    .map(String::clone)
+                    .map_err(|e| e.to_string()),
+            };
+            if let Some(log) = &mut self.log {
+                if let Err(e) = log.write(&entry) {
+                    println!(
+                        "{}",
+                        format!("Could not write to the session log. {}", e).yellow()
+                    );
+                }
+            }
+            self.transcript.push(entry);
+
+            match result {
+                Ok(res) => {
+                    let classify = GameMapper::get_classify_fn(self.client.game());
+                    self.record_outcome(command, classify(&res).error.is_none());
+                    self.print_command_response(res)?;
+                    self.print_timing(elapsed);
+                }
+                Err(e) if !e.is_fatal() => {
+                    self.record_outcome(command, false);
+                    println!("{}", e.to_string().red());
+                    self.print_timing(elapsed);
+                }
+                Err(e) => {
+                    println!("{}", format!("Lost connection to the server. {}", e).red());
+                    if self.reconnect().await && Self::confirm(&format!("Resend \"{}\"?", command))?
+                    {
+                        continue;
+                    }
+                }
+            }
+
+            return Ok(());
+        }
+    }
+
+    ///Remembers whether a command got an error back, so the hinter can prefer commands that worked
+    fn record_outcome(&mut self, command: &str, succeeded: bool) {
+        let mut failed = self.failed.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = match succeeded {
+            true => failed.remove(command),
+            false => failed.insert(command.to_string()),
+        };
+
+        if let (true, Some(history)) = (changed, &self.history) {
+            if let Err(e) = history.save_failed(&failed) {
+                let msg = format!("Could not write {}. {}", history.failed_path().display(), e);
+                println!("{}", msg.yellow());
+            }
+        }
+    }
+
+    fn print_timing(&self, elapsed: Duration) {
+        if self.timing {
+            println!("{}", format!("Took {} ms", elapsed.as_millis()).dark_grey());
+        }
+    }
+
+    ///Handles commands for the shell itself, which start with a colon and are never sent to the server. Returns whether
+    /// the shell should keep running.
+    async fn meta_command(&mut self, line: &str) -> Result<bool, RconError> {
+        let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+
+        match name {
+            "help" => Self::print_help(),
+            "history" => self.print_history(arg)?,
+            "reconnect" => {
+                self.reconnect().await;
+            }
+            "game" => self.switch_game(arg),
+            "clear" => self.line_editor.clear_screen()?,
+            "timing" => self.set_timing(arg),
+            "source" => return self.source(arg).await,
+            "save-transcript" => self.save_transcript(arg),
+            "quit" => return Ok(false),
+            _ => println!(
+                "{}",
+                format!("Unknown shell command :{}. :help lists them.", name).red()
+            ),
+        }
+
+        return Ok(true);
+    }
+
+    fn print_help() {
+        for command in META_COMMANDS {
+            let usage = format!(":{} {}", command.name, command.args);
+            println!("{}{}", format!("{:<24}", usage).bold(), command.description);
+        }
+    }
+
+    fn print_usage(name: &str) {
+        if let Some(command) = meta::find(name) {
+            let usage = format!("Usage: :{} {}", command.name, command.args);
+            println!("{}", usage.red());
+        }
+    }
+
+    ///Switches the game responses are formatted for, along with the commands that are completed and highlighted
+    fn switch_game(&mut self, name: &str) {
+        if name.is_empty() {
+            println!("Using the {} profile.", self.client.game());
+            return;
+        }
+
+        let Ok(game) = name.parse::<Game>() else {
+            let msg = format!("Unknown game {}, expected minecraft or generic.", name);
+            println!("{}", msg.red());
+            return;
+        };
+
+        self.client.set_game(game.clone());
+        let commands = self.client.commands();
+        //reedline only takes a new highlighter and completer by value
+        let line_editor = std::mem::replace(&mut self.line_editor, Reedline::create());
+        self.line_editor = line_editor
+            .with_highlighter(Box::new(RCONHighlighter::new(
+                commands.clone(),
+                game,
+                self.theme,
+            )))
+            .with_completer(Box::new(RCONCompleter::new(commands)));
+
+        println!("Switched to the {} profile.", self.client.game());
+    }
+
+    fn set_timing(&mut self, arg: &str) {
+        match arg {
+            "on" => self.timing = true,
+            "off" => self.timing = false,
+            "" => {}
+            _ => return Self::print_usage("timing"),
+        }
+
+        let state = if self.timing { "on" } else { "off" };
+        println!("Timing is {}.", state);
+    }
+
+    ///Runs the lines of a file as if they were typed, meta-commands included. Blank lines and lines starting with `#`
+    /// are skipped, like in exec scripts.
+    async fn source(&mut self, path: &str) -> Result<bool, RconError> {
+        if path.is_empty() {
+            Self::print_usage("source");
+            return Ok(true);
+        }
+        if self.sourcing {
+            println!("{}", "Can't :source from a sourced file.".red());
+            return Ok(true);
+        }
+
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("{}", format!("Could not read {}. {}", path, e).red());
+                return Ok(true);
+            }
+        };
+
+        self.sourcing = true;
+        let mut result = Ok(true);
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('@') {
+                let msg = format!("Skipping {}, directives only work in exec scripts.", line);
+                println!("{}", msg.yellow());
+                continue;
+            }
+
+            println!("{}", format!("> {}", line).bold());
+            result = self.run_line(line).await;
+            if !matches!(result, Ok(true)) {
+                break;
+            }
+        }
+        self.sourcing = false;
+
+        return result;
+    }
+
+    fn save_transcript(&self, path: &str) {
+        if path.is_empty() {
+            return Self::print_usage("save-transcript");
+        }
+
+        let contents: String = self
+            .transcript
+            .iter()
+            .map(transcript::Entry::to_text)
+            .collect();
+        match std::fs::write(path, contents) {
+            Ok(_) => println!("Saved {} commands to {}.", self.transcript.len(), path),
+            Err(e) => println!("{}", format!("Could not write {}. {}", path, e).red()),
+        }
+    }
+
+    fn save_history(&mut self) -> io::Result<()> {
+        self.line_editor.sync_history()?;
+        //reedline only skips a command repeated straight away, so earlier uses are dropped here. Syncing again reads
+        // the file back, which takes them out of the session's history too.
+        if let Some(history) = &self.history {
+            if history.remove_duplicates()? {
+                self.line_editor.sync_history()?;
+            }
+        }
+        return Ok(());
+    }
+
+    ///Lists this server's history, or searches the history of every server when given some text
+    fn print_history(&mut self, search: &str) -> io::Result<()> {
+        let Some(history) = &self.history else {
+            println!("{}", "History isn't being saved.".yellow());
+            return Ok(());
+        };
+
+        if search.is_empty() {
+            for (i, entry) in history.entries()?.iter().enumerate() {
+                println!("{:>5}  {}", i + 1, entry);
+            }
+            return Ok(());
+        }
+
+        for (server, entry) in history.search_all(search)? {
+            println!("{}  {}", format!("[{}]", server).bold(), entry);
+        }
+        Ok(())
+    }
+
+    ///Tries to reconnect and log in again with exponential backoff. Returns whether it succeeded.
+    async fn reconnect(&mut self) -> bool {
+        self.prompt.state = ConnectionState::Reconnecting;
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            self.print_state(&format!("attempt {}/{}...", attempt, RECONNECT_ATTEMPTS));
+
+            match self.client.reconnect().await {
+                Ok(_) => {
+                    println!("{}", "Reconnected.".green());
+                    self.prompt.state = ConnectionState::Connected;
+                    return true;
+                }
+                //Retrying with the same password is pointless
+                Err(RconError::AuthRejected) => {
+                    println!("{}", RconError::AuthRejected.to_string().red());
+                    break;
+                }
+                Err(e) => println!("{}", e.to_string().red()),
+            }
+
+            if attempt < RECONNECT_ATTEMPTS {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+
+        println!(
+            "{}",
+            "Could not reconnect. Enter a command to try again.".red()
+        );
+        self.prompt.state = ConnectionState::Disconnected;
+        return false;
+    }
+
+    ///reedline only draws the prompt while reading a line, so while the shell is busy the connection state is shown by
+    /// printing the left side of the prompt in its colour
+    fn print_state(&self, msg: &str) {
+        let line = format!("{} {}", self.prompt.render_prompt_left(), msg);
+        println!("{}", line.with(self.prompt.get_prompt_color()));
+    }
+
+    ///Asks a yes/no question on stdin, defaulting to no
+    fn confirm(question: &str) -> io::Result<bool> {
+        print!("{} [y/N] ", question);
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        return Ok(matches!(answer.trim(), "y" | "Y" | "yes"));
+    }
+
+    fn create_reedline(
+        commands: Vec<String>,
+        game: Game,
+        theme: Theme,
+        history: Option<FileBackedHistory>,
+        failed: FailedCommands,
+    ) -> Reedline {
+        let highlighter = RCONHighlighter::new(commands.clone(), game, theme);
+        let hinter = RCONHinter::new(theme, failed);
+        let completer = RCONCompleter::new(commands);
         let completion_menu = Box::new(
             ColumnarMenu::default()
                 .with_name("completion_menu")
@@ -89,15 +497,21 @@ impl RCONShell<'_> {
 
         let edit_mode = Box::new(Emacs::new(keybindings));
 
-        Reedline::create()
+        let line_editor = Reedline::create()
             .with_highlighter(Box::new(highlighter))
+            .with_hinter(Box::new(hinter))
             .with_edit_mode(edit_mode)
             .with_completer(Box::new(completer))
-            .with_menu(ReedlineMenu::EngineCompleter(completion_menu))
+            .with_menu(ReedlineMenu::EngineCompleter(completion_menu));
+
+        return match history {
+            Some(history) => line_editor.with_history(Box::new(history)),
+            None => line_editor,
+        };
     }
 
     fn print_command_response(&mut self, res: String) -> std::io::Result<()> {
-        let response_lines = (self.response_fn)(&res);
+        let response_lines = self.client.format_response(&res);
         for line in response_lines {
             let line_with_newline: String = line.0 + "\n";
             execute!(
@@ -105,7 +519,7 @@ impl RCONShell<'_> {
                 SetStyle(ContentStyle::new().attribute(Attribute::Reset))
             )?;
             execute!(self.stdout, SetStyle(line.1))?;
-            self.stdout.write(line_with_newline.as_bytes())?;
+            self.stdout.write_all(line_with_newline.as_bytes())?;
         }
         execute!(
             self.stdout,
@@ -117,23 +531,38 @@ impl RCONShell<'_> {
     }
 }
 
+#[derive(PartialEq)]
+enum ConnectionState {
+    Connected,
+    Reconnecting,
+    Disconnected,
+}
+
 struct RCONPrompt {
     prompt: String,
     left: String,
+    state: ConnectionState,
+    theme: Theme,
 }
 
 impl RCONPrompt {
-    fn create(ip: String) -> RCONPrompt {
+    fn create(ip: String, theme: Theme) -> RCONPrompt {
         return RCONPrompt {
             prompt: " >>".to_string(),
             left: "[".to_string() + &ip + "]",
+            state: ConnectionState::Connected,
+            theme,
         };
     }
 }
 
 impl Prompt for RCONPrompt {
     fn render_prompt_left(&self) -> Cow<'_, str> {
-        return Cow::from(self.left.as_str());
+        return match self.state {
+            ConnectionState::Connected => Cow::from(self.left.as_str()),
+            ConnectionState::Reconnecting => Cow::Owned(self.left.clone() + " (reconnecting)"),
+            ConnectionState::Disconnected => Cow::Owned(self.left.clone() + " (disconnected)"),
+        };
     }
 
     fn render_prompt_right(&self) -> Cow<'_, str> {
@@ -156,15 +585,20 @@ impl Prompt for RCONPrompt {
     }
 
     fn get_prompt_right_color(&self) -> reedline::Color {
-        reedline::Color::White
+        self.theme.text_color()
     }
 
     fn get_prompt_color(&self) -> reedline::Color {
-        reedline::Color::Green
+        let [connected, reconnecting, disconnected] = self.theme.prompt_colors();
+        match self.state {
+            ConnectionState::Connected => connected,
+            ConnectionState::Reconnecting => reconnecting,
+            ConnectionState::Disconnected => disconnected,
+        }
     }
 
     fn get_indicator_color(&self) -> reedline::Color {
-        reedline::Color::White
+        self.theme.text_color()
     }
 
     fn right_prompt_on_last_line(&self) -> bool {
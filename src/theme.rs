@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use nu_ansi_term::{Color, Style};
+use serde::{Deserialize, Serialize};
+
+///Colour schemes for the shell. The default one assumes a dark terminal background, on a light one its yellow and
+/// white are hard to read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    ///Colour of the prompt while connected, reconnecting and disconnected
+    pub fn prompt_colors(&self) -> [reedline::Color; 3] {
+        return match self {
+            Theme::Dark => [
+                reedline::Color::Green,
+                reedline::Color::Yellow,
+                reedline::Color::Red,
+            ],
+            Theme::Light => [
+                reedline::Color::DarkGreen,
+                reedline::Color::DarkYellow,
+                reedline::Color::DarkRed,
+            ],
+        };
+    }
+
+    ///Colour of the prompt indicator and the clock
+    pub fn text_color(&self) -> reedline::Color {
+        return match self {
+            Theme::Dark => reedline::Color::White,
+            Theme::Light => reedline::Color::Black,
+        };
+    }
+
+    pub fn command_style(&self) -> Style {
+        return match self {
+            Theme::Dark => Style::new().fg(Color::LightYellow),
+            Theme::Light => Style::new().fg(Color::Blue),
+        };
+    }
+
+    pub fn neutral_style(&self) -> Style {
+        return match self {
+            Theme::Dark => Style::new().fg(Color::LightGray),
+            Theme::Light => Style::new().fg(Color::DarkGray),
+        };
+    }
+
+    pub fn nomatch_style(&self) -> Style {
+        return Style::new().fg(Color::Red);
+    }
+
+    ///Colour of the suggestions taken from history, dimmer than anything typed
+    pub fn hint_style(&self) -> Style {
+        return match self {
+            Theme::Dark => Style::new().fg(Color::DarkGray),
+            Theme::Light => Style::new().fg(Color::LightGray),
+        };
+    }
+}
+
+///Required for argh
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Theme::Dark => write!(f, "dark"),
+            Theme::Light => write!(f, "light"),
+        }
+    }
+}
+
+///Required for argh
+impl FromStr for Theme {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!("Invalid theme {}, expected dark or light", s)),
+        }
+    }
+}
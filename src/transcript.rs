@@ -0,0 +1,184 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+///A command sent from the shell and the raw response body, or the error if no response came back
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    pub command: String,
+    pub response: Result<String, String>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    time: String,
+    command: &'a str,
+    body: Option<&'a str>,
+    error: Option<&'a str>,
+}
+
+impl Entry {
+    ///The command after a timestamp, then the response. Terminal styling is removed, since a transcript is meant to be
+    /// read in an editor or grepped.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("[{}] > {}\n", self.timestamp(), strip_ansi(&self.command));
+        match &self.response {
+            Ok(body) => text += &strip_ansi(body),
+            Err(e) => text += &format!("error: {}", strip_ansi(e)),
+        }
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        return text;
+    }
+
+    ///One JSON object on a single line, with the same fields as exec's JSON output where they overlap
+    pub fn to_json(&self) -> String {
+        let command = strip_ansi(&self.command);
+        let response = match &self.response {
+            Ok(body) => Ok(strip_ansi(body)),
+            Err(e) => Err(strip_ansi(e)),
+        };
+        let record = Record {
+            time: self.timestamp(),
+            command: &command,
+            body: response.as_deref().ok(),
+            error: response.as_ref().err().map(String::as_str),
+        };
+
+        return serde_json::to_string(&record).unwrap() + "\n";
+    }
+
+    fn timestamp(&self) -> String {
+        return self.time.to_rfc3339_opts(SecondsFormat::Secs, true);
+    }
+}
+
+//Colour and cursor sequences (CSI), and terminal titles and links (OSC)
+fn strip_ansi(text: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let regex = ANSI.get_or_init(|| {
+        return Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)?|\x1b[@-_]")
+            .unwrap();
+    });
+    return regex.replace_all(text, "").to_string();
+}
+
+///How a session log is written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    ///One JSON object per command
+    Jsonl,
+}
+
+///Required for argh
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+
+///Required for argh
+impl FromStr for LogFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "jsonl" => Ok(LogFormat::Jsonl),
+            _ => Err(format!("Invalid log format {}, expected text or jsonl", s)),
+        }
+    }
+}
+
+///A file the shell appends every command and response to as they happen, so nothing is lost if the session ends badly
+pub struct SessionLog {
+    file: File,
+    format: LogFormat,
+}
+
+impl SessionLog {
+    ///Opens the log for appending, creating it if needed. Earlier sessions in the file are kept.
+    pub fn open(path: &Path, format: LogFormat) -> io::Result<SessionLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        return Ok(SessionLog { file, format });
+    }
+
+    pub fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        let line = match self.format {
+            LogFormat::Text => entry.to_text(),
+            LogFormat::Jsonl => entry.to_json(),
+        };
+
+        //one write per entry, so two shells logging to the same file don't interleave within an entry
+        self.file.write_all(line.as_bytes())?;
+        return self.file.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    fn entry(response: Result<&str, &str>) -> Entry {
+        return Entry {
+            time: Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap(),
+            command: String::from("\x1b[1mlist\x1b[0m"),
+            response: response.map(String::from).map_err(String::from),
+        };
+    }
+
+    #[test]
+    fn strips_styling_from_text() {
+        //a colour, and a link to the player's profile
+        let body = "\x1b[31mThere are 1 players:\x1b[0m \x1b]8;;https://example.com\x1b\\Steve\x1b]8;;\x07";
+        assert_eq!(
+            entry(Ok(body)).to_text(),
+            "[2024-05-01T12:30:00Z] > list\nThere are 1 players: Steve\n"
+        );
+        assert_eq!(
+            entry(Err("\x1b[33mtimed out\x1b[0m")).to_text(),
+            "[2024-05-01T12:30:00Z] > list\nerror: timed out\n"
+        );
+    }
+
+    #[test]
+    fn writes_the_same_fields_as_exec() {
+        //command, body and error as exec's CommandRecord names them, plus the time
+        let line = entry(Ok("\x1b]0;title\x07\x1b[32mdone\x1b[0m")).to_json();
+        assert!(line.ends_with('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&line).unwrap(),
+            serde_json::json!({
+                "time": "2024-05-01T12:30:00Z",
+                "command": "list",
+                "body": "done",
+                "error": null,
+            })
+        );
+
+        let line = entry(Err("timed out")).to_json();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&line).unwrap(),
+            serde_json::json!({
+                "time": "2024-05-01T12:30:00Z",
+                "command": "list",
+                "body": null,
+                "error": "timed out",
+            })
+        );
+    }
+}
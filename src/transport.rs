@@ -0,0 +1,234 @@
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::client::WebPkiServerVerifier;
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::error::RconError;
+
+///The streams frcon can open itself. Plain RCON sends everything, including the password, in cleartext, so servers
+/// exposed to the internet are often put behind a TLS terminator like stunnel or Traefik.
+pub enum Transport {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            Transport::Tls(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Transport::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            Transport::Tls(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(s) => Pin::new(s).poll_flush(cx),
+            Transport::Tls(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            Transport::Tls(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+///How to verify the server when connecting over TLS. With nothing set, the certificate has to be signed by a
+/// well known CA and valid for the address connected to.
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    ///PEM file with the CA certificates to trust instead of the built in ones
+    pub ca_file: Option<PathBuf>,
+    ///SHA-256 fingerprint of the server's certificate, in hex with or without colons. On its own it replaces CA
+    /// verification, which is what makes self-signed certificates work. Together with `ca_file` both have to pass.
+    pub fingerprint: Option<String>,
+    ///Name to send as SNI and to verify the certificate against, if it differs from the address
+    pub server_name: Option<String>,
+}
+
+impl TlsOptions {
+    ///Builds everything needed to start a handshake with `address`. Done once per connection, so a bad CA file or
+    /// fingerprint is reported before connecting rather than on every reconnect.
+    pub(crate) fn connector(
+        &self,
+        address: &str,
+    ) -> Result<(TlsConnector, ServerName<'static>), RconError> {
+        let provider = Arc::new(crypto::ring::default_provider());
+
+        let roots = match &self.ca_file {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                let certs = CertificateDer::pem_file_iter(path).map_err(|e| {
+                    RconError::Tls(format!("could not read {}: {}", path.display(), e))
+                })?;
+                for cert in certs {
+                    let cert = cert.map_err(|e| {
+                        RconError::Tls(format!("could not read {}: {}", path.display(), e))
+                    })?;
+                    roots
+                        .add(cert)
+                        .map_err(|e| RconError::Tls(format!("invalid CA certificate: {}", e)))?;
+                }
+                Some(roots)
+            }
+            None => None,
+        };
+
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| RconError::Tls(e.to_string()))?;
+
+        let config = match &self.fingerprint {
+            Some(fingerprint) => {
+                let inner = match roots {
+                    Some(roots) => Some(
+                        WebPkiServerVerifier::builder_with_provider(
+                            Arc::new(roots),
+                            provider.clone(),
+                        )
+                        .build()
+                        .map_err(|e| RconError::Tls(e.to_string()))?,
+                    ),
+                    None => None,
+                };
+                let verifier = PinnedCertVerifier {
+                    fingerprint: parse_fingerprint(fingerprint)?,
+                    inner,
+                    provider,
+                };
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(verifier))
+                    .with_no_client_auth()
+            }
+            None => {
+                let roots = roots.unwrap_or_else(|| RootCertStore {
+                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                });
+                builder.with_root_certificates(roots).with_no_client_auth()
+            }
+        };
+
+        let name = self.server_name.as_deref().unwrap_or(address);
+        let server_name = ServerName::try_from(name.to_string())
+            .map_err(|_| RconError::Tls(format!("{} is not a valid server name", name)))?;
+
+        return Ok((TlsConnector::from(Arc::new(config)), server_name));
+    }
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<Vec<u8>, RconError> {
+    let hex: String = fingerprint.chars().filter(|c| *c != ':').collect();
+    let invalid = || RconError::Tls(format!("{} is not a SHA-256 fingerprint", fingerprint));
+
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    return (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+        .collect();
+}
+
+///Accepts a certificate if its fingerprint matches, after the usual checks when a CA file was given as well.
+/// The handshake signatures are still verified, so the server has to hold the certificate's private key.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: Vec<u8>,
+    inner: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(inner) = &self.inner {
+            inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        }
+
+        if Sha256::digest(end_entity.as_ref()).as_slice() != self.fingerprint.as_slice() {
+            return Err(rustls::Error::General(String::from(
+                "certificate doesn't match the pinned fingerprint",
+            )));
+        }
+
+        return Ok(ServerCertVerified::assertion());
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
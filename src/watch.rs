@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use crossterm::cursor::MoveTo;
+use crossterm::style::{Attribute, ContentStyle, SetStyle, Stylize};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{queue, style::Print};
+
+///Redraws the screen with the formatted response, highlighting lines that differ from the last run. Returns the lines
+/// drawn, to compare the next run against.
+pub fn draw(
+    out: &mut impl Write,
+    header: &str,
+    response: Vec<(String, ContentStyle)>,
+    previous: &[String],
+) -> std::io::Result<Vec<String>> {
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+    queue!(out, SetStyle(ContentStyle::new().bold()), Print(header))?;
+    queue!(
+        out,
+        SetStyle(ContentStyle::new().attribute(Attribute::Reset)),
+        Print("\n\n")
+    )?;
+
+    //formatters may put several lines in one styled section, and changes are shown per line
+    let mut lines = Vec::<String>::new();
+    for (section, style) in response {
+        for line in section.split('\n') {
+            //nothing to compare the first run against, so nothing is highlighted
+            let changed =
+                !previous.is_empty() && previous.get(lines.len()) != Some(&line.to_string());
+            let style = match changed {
+                true => style.attribute(Attribute::Reverse),
+                false => style,
+            };
+
+            queue!(
+                out,
+                SetStyle(ContentStyle::new().attribute(Attribute::Reset))
+            )?;
+            queue!(out, SetStyle(style), Print(line))?;
+            queue!(
+                out,
+                SetStyle(ContentStyle::new().attribute(Attribute::Reset)),
+                Print("\n")
+            )?;
+            lines.push(line.to_string());
+        }
+    }
+
+    out.flush()?;
+    return Ok(lines);
+}
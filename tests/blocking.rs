@@ -0,0 +1,91 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::io::Read;
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use frcon::blocking::RCONConnection;
+use frcon::RconError;
+
+use common::{serve, PASSWORD};
+
+///Starts the same server the async tests use on a thread of its own, so the blocking connection is checked against
+/// exactly the packets the async one gets. Serves one connection.
+fn start_server(respond: fn(&str) -> Vec<Vec<u8>>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_nonblocking(true).unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let stream = tokio::net::TcpStream::from_std(stream).unwrap();
+            serve(stream, respond).await;
+        });
+    });
+
+    return port;
+}
+
+fn connect(respond: fn(&str) -> Vec<Vec<u8>>) -> RCONConnection {
+    let port = start_server(respond);
+    return RCONConnection::new("127.0.0.1", port, Some(Duration::from_secs(5))).unwrap();
+}
+
+#[test]
+fn logs_in_and_sends_commands() {
+    let mut conn = connect(|command| vec![command.as_bytes().to_vec()]);
+    conn.auth(PASSWORD).unwrap();
+
+    assert_eq!(conn.send_command("list").unwrap(), "list");
+    assert_eq!(conn.send_command("say hi").unwrap(), "say hi");
+}
+
+#[test]
+fn rejects_a_wrong_password() {
+    let mut conn = connect(|_| Vec::new());
+    assert!(matches!(conn.auth("wrong"), Err(RconError::AuthRejected)));
+}
+
+#[test]
+fn reassembles_responses_split_over_packets() {
+    let mut conn = connect(|command| match command {
+        "help" => vec![
+            b"/advancement".to_vec(),
+            b"/ban".to_vec(),
+            b"/clear".to_vec(),
+        ],
+        _ => vec![command.as_bytes().to_vec()],
+    });
+    conn.auth(PASSWORD).unwrap();
+
+    assert_eq!(conn.send_command("help").unwrap(), "/advancement/ban/clear");
+    //the sentinel's reply ends the first response, so nothing of it leaks into the next
+    assert_eq!(conn.send_command("list").unwrap(), "list");
+}
+
+#[test]
+fn reports_a_read_timeout() {
+    //Accepts and reads, but never answers
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0u8; 1024];
+        while matches!(stream.read(&mut buf), Ok(n) if n > 0) {}
+    });
+
+    let mut conn = RCONConnection::new("127.0.0.1", port, Some(Duration::from_secs(5))).unwrap();
+    conn.set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+
+    assert!(matches!(conn.auth(PASSWORD), Err(RconError::Timeout)));
+}
@@ -0,0 +1,62 @@
+//Shared by the integration tests. Not every test file uses every helper.
+#![allow(dead_code)]
+
+use futures_util::StreamExt;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::Framed;
+
+use frcon::rcon::{RCONCommand, RconCodec};
+
+pub const PASSWORD: &str = "hunter2";
+
+//Packet types as the server sends them
+pub const RESPONSE_VALUE: i32 = 0;
+pub const AUTH_RESPONSE: i32 = 2;
+
+///Serializes a packet by hand, so tests can send bodies RCONPacket won't hold, like invalid UTF-8
+pub fn packet(id: i32, kind: i32, body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(10 + body.len() as i32).to_le_bytes());
+    buf.extend_from_slice(&id.to_le_bytes());
+    buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(body);
+    buf.extend_from_slice(&[0x00, 0x00]);
+    return buf;
+}
+
+///A minimal RCON server. Logs in anyone with [`PASSWORD`], answers each command with the bodies `respond` returns,
+/// one packet each, and mirrors sentinels like a Source server would.
+pub async fn serve<S>(stream: S, respond: impl Fn(&str) -> Vec<Vec<u8>>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut framed = Framed::new(stream, RconCodec::default());
+
+    while let Some(Ok(request)) = framed.next().await {
+        let mut reply = Vec::new();
+        //The codec reads packets as responses, so a command (type 2) shows up as an auth response
+        match request.command() {
+            RCONCommand::ServerAuth => {
+                let id = match request.body() == PASSWORD.as_bytes() {
+                    true => request.id(),
+                    false => -1,
+                };
+                reply.extend(packet(id, AUTH_RESPONSE, b""));
+            }
+            RCONCommand::ServerResponseValue => {
+                reply.extend(packet(request.id(), RESPONSE_VALUE, b""));
+            }
+            _ => {
+                let command = String::from_utf8_lossy(request.body()).to_string();
+                for body in respond(&command) {
+                    reply.extend(packet(request.id(), RESPONSE_VALUE, &body));
+                }
+            }
+        }
+
+        let stream = framed.get_mut();
+        if stream.write_all(&reply).await.is_err() {
+            return;
+        }
+    }
+}
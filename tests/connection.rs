@@ -0,0 +1,71 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use frcon::rcon::{Encoding, RCONConnection};
+use frcon::RconError;
+
+use common::{serve, PASSWORD};
+
+//The server end runs as a task, so the connection under test sees it as it would a socket
+fn connect(respond: fn(&str) -> Vec<Vec<u8>>) -> RCONConnection<tokio::io::DuplexStream> {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(serve(server, respond));
+
+    return RCONConnection::from_stream(client);
+}
+
+#[tokio::test]
+async fn invalid_utf8_only_fails_that_command() {
+    let mut conn = connect(|command| match command {
+        "bad" => vec![vec![0xff, 0xfe, 0xfd]],
+        _ => vec![command.as_bytes().to_vec()],
+    });
+    conn.set_encoding(Encoding::Strict);
+    conn.auth(PASSWORD).await.unwrap();
+
+    let err = conn.send_command("bad").await.unwrap_err();
+    assert!(matches!(err, RconError::InvalidUtf8));
+    assert!(!err.is_fatal());
+
+    assert_eq!(conn.send_command("hello").await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn strict_accepts_characters_split_between_packets() {
+    //"é" is 0xc3 0xa9, cut in half like a server splitting at a byte limit would
+    let mut conn = connect(|_| vec![b"caf\xc3".to_vec(), b"\xa9".to_vec()]);
+    conn.set_encoding(Encoding::Strict);
+    conn.auth(PASSWORD).await.unwrap();
+
+    assert_eq!(conn.send_command("list").await.unwrap(), "café");
+}
+
+#[tokio::test]
+async fn reassembles_responses_split_over_packets() {
+    let mut conn = connect(|command| match command {
+        "help" => vec![
+            b"/advancement".to_vec(),
+            b"/ban".to_vec(),
+            b"/clear".to_vec(),
+        ],
+        _ => vec![command.as_bytes().to_vec()],
+    });
+    conn.auth(PASSWORD).await.unwrap();
+
+    assert_eq!(
+        conn.send_command("help").await.unwrap(),
+        "/advancement/ban/clear"
+    );
+    //the sentinel's reply ends the first response, so nothing of it leaks into the next
+    assert_eq!(conn.send_command("list").await.unwrap(), "list");
+}
+
+#[tokio::test]
+async fn rejects_a_wrong_password() {
+    let mut conn = connect(|_| Vec::new());
+    assert!(matches!(
+        conn.auth("wrong").await,
+        Err(RconError::AuthRejected)
+    ));
+}
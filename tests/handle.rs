@@ -0,0 +1,80 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use tokio::net::TcpListener;
+
+use frcon::{RconClient, RconError, RconHandle};
+
+use common::{serve, PASSWORD};
+
+///Starts a server on localhost which answers each command in one packet per character, so responses that got mixed
+/// up between requests wouldn't come back whole
+async fn start_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve(stream, |command| {
+                return command.bytes().map(|b| vec![b]).collect();
+            }));
+        }
+    });
+
+    return port;
+}
+
+async fn spawn_client(port: u16) -> RconHandle {
+    let client = RconClient::builder()
+        .address("127.0.0.1")
+        .port(port)
+        .password(PASSWORD)
+        .connect()
+        .await
+        .unwrap();
+
+    return client.spawn();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn clones_get_their_own_responses() {
+    let handle = spawn_client(start_server().await).await;
+
+    let tasks: Vec<_> = (0..16)
+        .map(|i| {
+            let handle = handle.clone();
+            return tokio::spawn(async move {
+                let command = format!("say message {}", i);
+                return (handle.send_command(&command).await.unwrap(), command);
+            });
+        })
+        .collect();
+
+    for task in tasks {
+        let (response, command) = task.await.unwrap();
+        assert_eq!(response, command);
+    }
+}
+
+#[tokio::test]
+async fn reconnects_between_commands() {
+    let handle = spawn_client(start_server().await).await;
+
+    assert_eq!(handle.send_command("list").await.unwrap(), "list");
+    handle.reconnect().await.unwrap();
+    assert_eq!(handle.send_command("list").await.unwrap(), "list");
+}
+
+#[test]
+fn reports_disconnected_once_the_task_is_gone() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handle = runtime.block_on(async { spawn_client(start_server().await).await });
+    //Shutting the runtime down drops the client's task, while the handle lives on
+    drop(runtime);
+
+    let result = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(handle.send_command("list"));
+    assert!(matches!(result, Err(RconError::Disconnected)));
+}
@@ -0,0 +1,149 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+use frcon::rcon::RCONConnection;
+use frcon::{RconError, TlsOptions};
+
+use common::{serve, PASSWORD};
+
+//The name on the test certificate. It doesn't resolve, so it can only be used through server_name.
+const NAME: &str = "rcon.test";
+
+struct Server {
+    port: u16,
+    cert_pem: String,
+    fingerprint: String,
+}
+
+///Starts a TLS terminated RCON server on localhost with a freshly generated self-signed certificate. It answers `sni`
+/// with the name the client asked for, and echoes anything else.
+async fn start_server() -> Server {
+    let key = rcgen::generate_simple_self_signed(vec![NAME.to_string()]).unwrap();
+    let cert_der = key.cert.der().clone();
+    let fingerprint = Sha256::digest(&cert_der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let private_key =
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.signing_key.serialize_der()));
+    let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der], private_key)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                //failed handshakes are what some of the tests are after
+                let Ok(stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let sni = stream.get_ref().1.server_name().unwrap_or("").to_string();
+                serve(stream, move |command| match command {
+                    "sni" => vec![sni.as_bytes().to_vec()],
+                    _ => vec![command.as_bytes().to_vec()],
+                })
+                .await;
+            });
+        }
+    });
+
+    return Server {
+        port,
+        cert_pem: key.cert.pem(),
+        fingerprint,
+    };
+}
+
+async fn connect(server: &Server, tls: TlsOptions) -> Result<RCONConnection, RconError> {
+    return RCONConnection::new_tls("127.0.0.1", server.port, None, &tls).await;
+}
+
+#[tokio::test]
+async fn accepts_a_matching_fingerprint() {
+    let server = start_server().await;
+    let tls = TlsOptions {
+        fingerprint: Some(server.fingerprint.clone()),
+        ..TlsOptions::default()
+    };
+
+    let mut conn = connect(&server, tls).await.unwrap();
+    conn.auth(PASSWORD).await.unwrap();
+    assert_eq!(conn.send_command("list").await.unwrap(), "list");
+}
+
+#[tokio::test]
+async fn rejects_a_different_fingerprint() {
+    let server = start_server().await;
+    let tls = TlsOptions {
+        fingerprint: Some("00".repeat(32)),
+        ..TlsOptions::default()
+    };
+
+    assert!(matches!(
+        connect(&server, tls).await,
+        Err(RconError::Tls(_))
+    ));
+}
+
+#[tokio::test]
+async fn rejects_a_self_signed_certificate_without_a_ca_file() {
+    let server = start_server().await;
+    let tls = TlsOptions {
+        server_name: Some(NAME.to_string()),
+        ..TlsOptions::default()
+    };
+
+    assert!(matches!(
+        connect(&server, tls).await,
+        Err(RconError::Tls(_))
+    ));
+}
+
+#[tokio::test]
+async fn verifies_against_a_ca_file_and_server_name() {
+    let server = start_server().await;
+    let ca_file = std::env::temp_dir().join(format!("frcon-test-ca-{}.pem", server.port));
+    std::fs::write(&ca_file, &server.cert_pem).unwrap();
+
+    //Without server_name the certificate is checked against 127.0.0.1, which it isn't valid for
+    let tls = TlsOptions {
+        ca_file: Some(ca_file.clone()),
+        ..TlsOptions::default()
+    };
+    assert!(matches!(
+        connect(&server, tls).await,
+        Err(RconError::Tls(_))
+    ));
+
+    let tls = TlsOptions {
+        ca_file: Some(ca_file.clone()),
+        server_name: Some(NAME.to_string()),
+        ..TlsOptions::default()
+    };
+    let result = connect(&server, tls).await;
+    std::fs::remove_file(&ca_file).unwrap();
+
+    let mut conn = result.unwrap();
+    conn.auth(PASSWORD).await.unwrap();
+    assert_eq!(conn.send_command("sni").await.unwrap(), NAME);
+}